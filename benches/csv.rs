@@ -0,0 +1,46 @@
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use csv::Reader;
+use rstool::{csv_to_json, csv_to_json_parallel};
+use std::hint::black_box;
+
+const JUVENTUS: &str = include_str!("../assets/juventus.csv");
+
+/// Repeat the juventus rows until the input has at least `rows` records.
+fn make_input(rows: usize) -> String {
+    let mut lines = JUVENTUS.lines();
+    let header = lines.next().expect("juventus.csv has a header");
+    let body: Vec<&str> = lines.collect();
+
+    let mut data = String::with_capacity(rows * 64);
+    data.push_str(header);
+    data.push('\n');
+    for line in body.iter().cycle().take(rows) {
+        data.push_str(line);
+        data.push('\n');
+    }
+    data
+}
+
+fn bench_csv_to_json(c: &mut Criterion) {
+    let mut group = c.benchmark_group("csv_to_json");
+    group.sample_size(20);
+
+    for rows in [10_000, 100_000] {
+        let data = make_input(rows);
+        group.throughput(Throughput::Bytes(data.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("sequential", rows), &data, |b, data| {
+            b.iter(|| csv_to_json(Reader::from_reader(black_box(data.as_bytes()))).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("parallel", rows), &data, |b, data| {
+            b.iter(|| {
+                csv_to_json_parallel(Reader::from_reader(black_box(data.as_bytes())), 0).unwrap()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_csv_to_json);
+criterion_main!(benches);
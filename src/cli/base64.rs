@@ -16,6 +16,7 @@ pub struct Base64EncodeOpts {
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
 
+    /// standard, standard-nopad, urlsafe (unpadded), urlsafe-pad, bcrypt or crypt
     #[arg(long, value_parser = parse_base64_format, default_value = "standard")]
     pub format: Base64Format,
 }
@@ -25,6 +26,12 @@ pub struct Base64DecodeOpts {
     #[arg(short, long, value_parser = verify_file, default_value = "-")]
     pub input: String,
 
+    /// Write the decoded bytes verbatim to this file, "-" for stdout
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+
+    /// Any encode format, or auto to detect the standard or URL-safe alphabet
+    /// and padding
     #[arg(long, value_parser = parse_base64_format, default_value = "standard")]
     pub format: Base64Format,
 }
@@ -32,7 +39,12 @@ pub struct Base64DecodeOpts {
 #[derive(Debug, Parser, Copy, Clone)]
 pub enum Base64Format {
     Standard,
+    StandardNoPad,
     UrlSafe,
+    UrlSafePad,
+    Bcrypt,
+    Crypt,
+    Auto,
 }
 
 fn parse_base64_format(s: &str) -> Result<Base64Format, anyhow::Error> {
@@ -45,7 +57,12 @@ impl FromStr for Base64Format {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "standard" => Ok(Base64Format::Standard),
+            "standard-nopad" => Ok(Base64Format::StandardNoPad),
             "urlsafe" => Ok(Base64Format::UrlSafe),
+            "urlsafe-pad" => Ok(Base64Format::UrlSafePad),
+            "bcrypt" => Ok(Base64Format::Bcrypt),
+            "crypt" => Ok(Base64Format::Crypt),
+            "auto" => Ok(Base64Format::Auto),
             _ => Err(anyhow::anyhow!("Invalid format: {}", s)),
         }
     }
@@ -55,16 +72,18 @@ impl From<Base64Format> for &'static str {
     fn from(format: Base64Format) -> Self {
         match format {
             Base64Format::Standard => "standard",
+            Base64Format::StandardNoPad => "standard-nopad",
             Base64Format::UrlSafe => "urlsafe",
+            Base64Format::UrlSafePad => "urlsafe-pad",
+            Base64Format::Bcrypt => "bcrypt",
+            Base64Format::Crypt => "crypt",
+            Base64Format::Auto => "auto",
         }
     }
 }
 
 impl fmt::Display for Base64Format {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Base64Format::Standard => write!(f, "standard"),
-            Base64Format::UrlSafe => write!(f, "urlsafe"),
-        }
+        write!(f, "{}", Into::<&str>::into(*self))
     }
 }
@@ -10,18 +10,29 @@ use std::{fmt, str::FromStr};
  *   通过为 OutputFormat 添加 #[derive(Copy, Clone)]，您告诉 Rust：
  *   Copy：这个类型可以通过简单的内存复制来复制（适用于小型的、简单的类型）
  *   Clone：这个类型可以通过调用 clone() 方法来复制
- *   由于 OutputFormat 是一个简单的枚举（只包含两个变体，没有关联数据），它非常适合使用 Copy trait。
+ *   由于 OutputFormat 是一个简单的枚举（变体都不带关联数据），它非常适合使用 Copy trait。
  */
 #[derive(Debug, Parser, Copy, Clone)]
 pub enum OutputFormat {
     Json,
     Yaml,
+    Xlsx,
+    Xml,
+    MsgPack,
+    Cbor,
 }
 
 #[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct CsvOpts {
-    #[arg(short, long, value_parser = verify_file)]
-    pub input: String,
+    #[command(subcommand)]
+    pub cmd: Option<CsvSubCommand>,
+    // required unless a subcommand is given
+    #[arg(short, long, value_parser = verify_file, required = true)]
+    pub input: Option<String>,
+    /// Worksheet to read when the input is an Excel workbook (defaults to the first one)
+    #[arg(long)]
+    pub sheet: Option<String>,
     #[arg(short, long)]
     pub output: Option<String>,
     #[arg(long, value_parser = parse_format, default_value = "json")]
@@ -30,6 +41,115 @@ pub struct CsvOpts {
     pub delimiter: char,
     #[arg(long, default_value_t = true)]
     pub header: bool,
+    /// Root element name for xml output
+    #[arg(long, default_value = "records")]
+    pub root: String,
+    /// Element name of each record for xml output
+    #[arg(long, default_value = "record")]
+    pub row: String,
+    /// Write fields as attributes instead of child elements for xml output
+    #[arg(long)]
+    pub xml_attributes: bool,
+    /// Convert records on a worker pool
+    #[arg(long)]
+    pub parallel: bool,
+    /// Number of worker threads for --parallel (defaults to the number of CPUs)
+    #[arg(long, requires = "parallel")]
+    pub jobs: Option<usize>,
+}
+
+#[derive(Debug, Parser)]
+pub enum CsvSubCommand {
+    #[command(name = "pivot", about = "Pivot a long csv into a wide one")]
+    Pivot(CsvPivotOpts),
+    #[command(name = "melt", about = "Melt a wide csv into a long one")]
+    Melt(CsvMeltOpts),
+    #[command(name = "render", about = "Render a template once per csv record")]
+    Render(CsvRenderOpts),
+    #[command(
+        name = "fake",
+        about = "Generate synthetic csv data from a yaml schema"
+    )]
+    Fake(CsvFakeOpts),
+    #[command(name = "decode", about = "Pretty-print converted records as json")]
+    Decode(CsvDecodeOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvPivotOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    /// Columns identifying a row, may be repeated
+    #[arg(long, required = true)]
+    pub index: Vec<String>,
+    /// Column whose values become the new headers
+    #[arg(long)]
+    pub columns: String,
+    /// Column holding the cell values
+    #[arg(long)]
+    pub values: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvMeltOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    /// Columns identifying a row, may be repeated
+    #[arg(long, required = true)]
+    pub index: Vec<String>,
+    /// Columns to unpivot (defaults to every non-index column)
+    #[arg(long)]
+    pub value_vars: Vec<String>,
+    #[arg(long, default_value = "variable")]
+    pub var_name: String,
+    #[arg(long, default_value = "value")]
+    pub value_name: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvRenderOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+    /// Worksheet to read when the input is an Excel workbook (defaults to the first one)
+    #[arg(long)]
+    pub sheet: Option<String>,
+    #[arg(short, long, value_parser = verify_file)]
+    pub template: String,
+    /// Write all rendered records to one output
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    /// Write one file per record, e.g. "out/{{Name}}.txt"; only the file name may
+    /// use placeholders
+    #[arg(long, conflicts_with = "output")]
+    pub output_pattern: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvFakeOpts {
+    /// Column definitions, see fixtures/fake_schema.yaml
+    #[arg(short, long, value_parser = verify_file)]
+    pub schema: String,
+    #[arg(short, long, default_value_t = 100)]
+    pub rows: usize,
+    /// Seed the generator for reproducible output
+    #[arg(long)]
+    pub seed: Option<u64>,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvDecodeOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+    #[arg(long, value_parser = parse_format, default_value = "msgpack")]
+    pub format: OutputFormat,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
 }
 
 fn parse_format(s: &str) -> Result<OutputFormat, anyhow::Error> {
@@ -42,6 +162,10 @@ impl From<OutputFormat> for &'static str {
         match format {
             OutputFormat::Json => "json",
             OutputFormat::Yaml => "yaml",
+            OutputFormat::Xlsx => "xlsx",
+            OutputFormat::Xml => "xml",
+            OutputFormat::MsgPack => "msgpack",
+            OutputFormat::Cbor => "cbor",
         }
     }
 }
@@ -53,6 +177,10 @@ impl FromStr for OutputFormat {
         match s {
             "json" => Ok(OutputFormat::Json),
             "yaml" => Ok(OutputFormat::Yaml),
+            "xlsx" => Ok(OutputFormat::Xlsx),
+            "xml" => Ok(OutputFormat::Xml),
+            "msgpack" => Ok(OutputFormat::MsgPack),
+            "cbor" => Ok(OutputFormat::Cbor),
             _ => Err(anyhow::anyhow!("Invalid output format: {}", s)),
         }
     }
@@ -63,6 +191,10 @@ impl fmt::Display for OutputFormat {
         match self {
             OutputFormat::Json => write!(f, "json"),
             OutputFormat::Yaml => write!(f, "yaml"),
+            OutputFormat::Xlsx => write!(f, "xlsx"),
+            OutputFormat::Xml => write!(f, "xml"),
+            OutputFormat::MsgPack => write!(f, "msgpack"),
+            OutputFormat::Cbor => write!(f, "cbor"),
         }
     }
 }
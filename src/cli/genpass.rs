@@ -1,15 +1,337 @@
-use clap::Parser;
+use super::verify_file;
+use crate::process::GenPassConfig;
+use clap::{ArgAction, ArgGroup, Args, Parser};
+use std::{fmt, str::FromStr};
 
+// the rule options are shared with site, which has no --policy, so the
+// conflict is declared here rather than on either flattened struct
 #[derive(Debug, Parser)]
+#[command(
+    args_conflicts_with_subcommands = true,
+    group(
+        ArgGroup::new("policy_rules")
+            .arg("policy")
+            .conflicts_with_all([
+                "length",
+                "uppercase",
+                "lowercase",
+                "numbers",
+                "symbols",
+                "charset",
+                "exclude",
+                "symbols_set",
+                "min_upper",
+                "min_lower",
+                "min_digits",
+                "min_symbols",
+            ])
+    )
+)]
 pub struct GenPassOpts {
+    #[command(subcommand)]
+    pub cmd: Option<GenPassSubCommand>,
+    #[command(flatten)]
+    pub rules: GenPassRuleOpts,
+    #[command(flatten)]
+    pub policy: GenPassPolicyOpts,
+    /// Generate a password shaped like "Cvcc-9{4}-SSS": C/c consonant, V/v vowel,
+    /// 9 digit, S symbol, a backslash escapes, 9{4} repeats, anything else is literal
+    #[arg(
+        long,
+        conflicts_with_all = ["passphrase", "policy"],
+        allow_hyphen_values = true
+    )]
+    pub pattern: Option<String>,
+    /// Generate a diceware-style passphrase instead of a password
+    #[arg(long, conflicts_with = "policy")]
+    pub passphrase: bool,
+    /// Alternate consonants and vowels so the password is easy to read out
+    #[arg(long, conflicts_with_all = ["pattern", "passphrase"])]
+    pub pronounceable: bool,
+    #[arg(long, default_value_t = 6, requires = "passphrase")]
+    pub words: usize,
+    #[arg(long, default_value = "-", requires = "passphrase")]
+    pub separator: String,
+    #[arg(long, requires = "passphrase")]
+    pub capitalize: bool,
+    #[arg(long, requires = "passphrase")]
+    pub append_digit: bool,
+    /// Wordlist file, one word per line or the EFF "11111<TAB>word" format
+    #[arg(long, value_parser = verify_file, requires = "passphrase")]
+    pub wordlist: Option<String>,
+    /// Regenerate until the zxcvbn score (0-4) is at least this
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=4))]
+    pub min_score: Option<u8>,
+    /// Context words for zxcvbn such as user names, comma separated
+    #[arg(long, value_delimiter = ',')]
+    pub user_inputs: Vec<String>,
+    /// Print a full strength report to stderr (text or json)
+    #[arg(
+        long,
+        value_parser = parse_report_format,
+        num_args = 0..=1,
+        default_missing_value = "text"
+    )]
+    pub report: Option<ReportFormat>,
+    /// Regenerate passwords found in this Pwned Passwords SHA-1 list
+    #[arg(long, value_parser = verify_file)]
+    pub hibp: Option<String>,
+    /// Number of passwords to generate, at most 10000
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=10_000))]
+    pub count: u32,
+    /// Output format, json and csv include entropy and strength score
+    #[arg(long, value_parser = parse_genpass_format, default_value = "text")]
+    pub format: GenPassFormat,
+    /// Seed the generator for reproducible output, for testing only
+    #[arg(long, hide = true)]
+    pub seed: Option<u64>,
+}
+
+/// Length and character class rules shared by the generating commands.
+#[derive(Debug, Args)]
+pub struct GenPassRuleOpts {
     #[arg(short, long, default_value_t = 16)]
-    pub length: u8,
-    #[arg(long, default_value_t = true)]
+    pub length: usize,
+    // --uppercase, --uppercase=false, ...
+    #[arg(
+        long,
+        default_value_t = true,
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        default_missing_value = "true"
+    )]
     pub uppercase: bool,
-    #[arg(long, default_value_t = true)]
+    #[arg(
+        long,
+        default_value_t = true,
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        default_missing_value = "true"
+    )]
     pub lowercase: bool,
-    #[arg(long, default_value_t = true)]
+    #[arg(
+        long,
+        default_value_t = true,
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        default_missing_value = "true"
+    )]
     pub numbers: bool,
-    #[arg(long, default_value_t = true)]
+    #[arg(
+        long,
+        default_value_t = true,
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        default_missing_value = "true"
+    )]
     pub symbols: bool,
+    /// Draw from this character set instead of the built-in classes
+    #[arg(long, conflicts_with = "symbols_set", allow_hyphen_values = true)]
+    pub charset: Option<String>,
+    /// Characters to leave out, e.g. "0O1lI"
+    #[arg(long, default_value = "", allow_hyphen_values = true)]
+    pub exclude: String,
+    /// Symbols to use instead of the built-in "!@#$%^&*_"
+    #[arg(long, allow_hyphen_values = true)]
+    pub symbols_set: Option<String>,
+    #[arg(long, default_value_t = 0)]
+    pub min_upper: usize,
+    #[arg(long, default_value_t = 0)]
+    pub min_lower: usize,
+    #[arg(long, default_value_t = 0)]
+    pub min_digits: usize,
+    #[arg(long, default_value_t = 0)]
+    pub min_symbols: usize,
+}
+
+/// A named policy from a YAML or TOML policy file.
+#[derive(Debug, Args)]
+pub struct GenPassPolicyOpts {
+    /// Name of the password policy to generate for or check against, replaces
+    /// the length and character class options
+    #[arg(long)]
+    pub policy: Option<String>,
+    /// Policy file, TOML if it ends in .toml and YAML otherwise
+    #[arg(long, default_value = "policies.yaml", requires = "policy")]
+    pub policy_file: String,
+}
+
+impl From<&GenPassRuleOpts> for GenPassConfig {
+    fn from(opts: &GenPassRuleOpts) -> Self {
+        Self {
+            length: opts.length,
+            upper: opts.uppercase,
+            lower: opts.lowercase,
+            number: opts.numbers,
+            symbol: opts.symbols,
+            charset: opts.charset.clone(),
+            symbols_set: opts.symbols_set.clone(),
+            exclude: opts.exclude.clone(),
+            min_upper: opts.min_upper,
+            min_lower: opts.min_lower,
+            min_number: opts.min_digits,
+            min_symbol: opts.min_symbols,
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub enum GenPassSubCommand {
+    #[command(name = "check", about = "Audit existing passwords, one per line")]
+    Check(GenPassCheckOpts),
+    #[command(name = "site", about = "Derive a site password from a master secret")]
+    Site(GenPassSiteOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct GenPassCheckOpts {
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    /// Context words for zxcvbn such as user names, comma separated
+    #[arg(long, value_delimiter = ',')]
+    pub user_inputs: Vec<String>,
+    /// Print the passwords instead of masking them
+    #[arg(long)]
+    pub show: bool,
+    /// Downloaded Pwned Passwords SHA-1 list, ordered by hash
+    #[arg(long, value_parser = verify_file)]
+    pub hibp: Option<String>,
+    #[command(flatten)]
+    pub policy: GenPassPolicyOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct GenPassSiteOpts {
+    /// Site name, e.g. example.com
+    #[arg(long)]
+    pub site: String,
+    #[arg(long)]
+    pub login: String,
+    /// Bump to rotate the password without changing the master secret
+    #[arg(long, default_value_t = 1)]
+    pub counter: u32,
+    /// File holding the master secret, "-" reads it from stdin
+    #[arg(long, value_parser = verify_file, default_value = "-")]
+    pub master: String,
+    /// Key derivation: blake3, or argon2 to slow down guessing the master secret
+    #[arg(long, value_parser = parse_site_kdf, default_value = "blake3")]
+    pub kdf: SiteKdf,
+    #[command(flatten)]
+    pub rules: GenPassRuleOpts,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SiteKdf {
+    Blake3,
+    Argon2,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum GenPassFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ReportFormat {
+    Text,
+    Json,
+}
+
+fn parse_genpass_format(s: &str) -> Result<GenPassFormat, anyhow::Error> {
+    s.parse()
+}
+
+impl FromStr for GenPassFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(GenPassFormat::Text),
+            "json" => Ok(GenPassFormat::Json),
+            "csv" => Ok(GenPassFormat::Csv),
+            _ => Err(anyhow::anyhow!("Invalid format: {}", s)),
+        }
+    }
+}
+
+impl From<GenPassFormat> for &'static str {
+    fn from(value: GenPassFormat) -> Self {
+        match value {
+            GenPassFormat::Text => "text",
+            GenPassFormat::Json => "json",
+            GenPassFormat::Csv => "csv",
+        }
+    }
+}
+
+impl fmt::Display for GenPassFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+fn parse_site_kdf(s: &str) -> Result<SiteKdf, anyhow::Error> {
+    s.parse()
+}
+
+impl FromStr for SiteKdf {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blake3" => Ok(SiteKdf::Blake3),
+            "argon2" => Ok(SiteKdf::Argon2),
+            _ => Err(anyhow::anyhow!("Invalid kdf: {}", s)),
+        }
+    }
+}
+
+impl From<SiteKdf> for &'static str {
+    fn from(value: SiteKdf) -> Self {
+        match value {
+            SiteKdf::Blake3 => "blake3",
+            SiteKdf::Argon2 => "argon2",
+        }
+    }
+}
+
+impl fmt::Display for SiteKdf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+fn parse_report_format(s: &str) -> Result<ReportFormat, anyhow::Error> {
+    s.parse()
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(ReportFormat::Text),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(anyhow::anyhow!("Invalid report format: {}", s)),
+        }
+    }
+}
+
+impl From<ReportFormat> for &'static str {
+    fn from(value: ReportFormat) -> Self {
+        match value {
+            ReportFormat::Text => "text",
+            ReportFormat::Json => "json",
+        }
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
 }
@@ -0,0 +1,106 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::verify_file;
+use clap::Parser;
+
+#[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct HashPwOpts {
+    #[command(subcommand)]
+    pub cmd: Option<HashPwSubCommand>,
+    #[arg(long, value_parser = parse_hash_algorithm, default_value = "argon2id")]
+    pub algo: HashAlgorithm,
+    /// File holding the password, "-" reads it from stdin
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+    /// Hash a freshly generated password instead, printed to stderr
+    #[arg(long, conflicts_with = "input")]
+    pub generate: bool,
+    /// Length of the generated password
+    #[arg(long, default_value_t = 16, requires = "generate")]
+    pub length: usize,
+    /// argon2id memory cost in KiB
+    #[arg(long)]
+    pub m: Option<u32>,
+    /// argon2id iterations
+    #[arg(long)]
+    pub t: Option<u32>,
+    /// argon2id or scrypt parallelism
+    #[arg(long)]
+    pub p: Option<u32>,
+    /// bcrypt cost, 4 to 31
+    #[arg(long)]
+    pub cost: Option<u32>,
+    /// scrypt log2 of N
+    #[arg(long)]
+    pub log_n: Option<u8>,
+    /// scrypt block size
+    #[arg(long)]
+    pub r: Option<u32>,
+    /// pbkdf2-sha256 iterations
+    #[arg(long)]
+    pub rounds: Option<u32>,
+    /// Seed the salt and --generate for reproducible output, for testing only
+    #[arg(long, hide = true)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Parser)]
+pub enum HashPwSubCommand {
+    #[command(name = "verify", about = "Check a password against a stored hash")]
+    Verify(HashPwVerifyOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct HashPwVerifyOpts {
+    /// PHC string, or a $2b$ style bcrypt hash
+    #[arg(long)]
+    pub hash: String,
+    /// File holding the password, "-" reads it from stdin
+    #[arg(short, long, value_parser = verify_file, default_value = "-")]
+    pub input: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum HashAlgorithm {
+    Argon2id,
+    Bcrypt,
+    Scrypt,
+    Pbkdf2,
+}
+
+fn parse_hash_algorithm(s: &str) -> Result<HashAlgorithm, anyhow::Error> {
+    s.parse()
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "argon2id" => Ok(HashAlgorithm::Argon2id),
+            "bcrypt" => Ok(HashAlgorithm::Bcrypt),
+            "scrypt" => Ok(HashAlgorithm::Scrypt),
+            "pbkdf2" => Ok(HashAlgorithm::Pbkdf2),
+            _ => Err(anyhow::anyhow!("Invalid algorithm: {}", s)),
+        }
+    }
+}
+
+impl From<HashAlgorithm> for &'static str {
+    fn from(algo: HashAlgorithm) -> Self {
+        match algo {
+            HashAlgorithm::Argon2id => "argon2id",
+            HashAlgorithm::Bcrypt => "bcrypt",
+            HashAlgorithm::Scrypt => "scrypt",
+            HashAlgorithm::Pbkdf2 => "pbkdf2",
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
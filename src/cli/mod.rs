@@ -1,7 +1,10 @@
 mod base64;
 mod csv;
 mod genpass;
+mod hashpw;
+mod otp;
 mod text;
+mod token;
 
 use clap::Parser;
 use std::path::Path;
@@ -10,10 +13,24 @@ use std::path::PathBuf;
 pub use base64::Base64Format;
 pub use base64::Base64SubCommand;
 pub use csv::CsvOpts;
+pub use csv::CsvSubCommand;
 pub use csv::OutputFormat;
+pub use genpass::GenPassFormat;
 pub use genpass::GenPassOpts;
+pub use genpass::GenPassPolicyOpts;
+pub use genpass::GenPassSubCommand;
+pub use genpass::ReportFormat;
+pub use genpass::SiteKdf;
+pub use hashpw::HashAlgorithm;
+pub use hashpw::HashPwOpts;
+pub use hashpw::HashPwSubCommand;
+pub use otp::OtpAlgorithm;
+pub use otp::OtpParams;
+pub use otp::OtpSubCommand;
 pub use text::TextSignFormat;
 pub use text::TextSubCommand;
+pub use token::TokenKind;
+pub use token::TokenOpts;
 
 #[derive(Debug, Parser)]
 #[command(name = "rstool", version, author, about, long_about = None)]
@@ -26,13 +43,19 @@ pub struct Opts {
 #[derive(Debug, Parser)]
 pub enum SubCommand {
     #[command(name = "csv", about = "Show csv, or convert csv to other formats")]
-    Csv(CsvOpts),
-    #[command(name = "genpass", about = "Generate a random password")]
-    GenPass(GenPassOpts),
+    Csv(Box<CsvOpts>),
+    #[command(name = "genpass", about = "Generate or check passwords")]
+    GenPass(Box<GenPassOpts>),
     #[command(subcommand, about = "Encode or decode a base64 string")]
     Base64(Base64SubCommand),
     #[command(subcommand, about = "Sign or verify a text")]
     Text(TextSubCommand),
+    #[command(name = "token", about = "Generate random machine tokens")]
+    Token(TokenOpts),
+    #[command(subcommand, about = "Generate or verify one-time passwords")]
+    Otp(OtpSubCommand),
+    #[command(name = "hashpw", about = "Hash a password for storage or verify it")]
+    HashPw(HashPwOpts),
 }
 
 pub fn verify_file(filename: &str) -> Result<String, &'static str> {
@@ -0,0 +1,112 @@
+use std::fmt;
+use std::str::FromStr;
+
+use clap::{Args, Parser};
+
+#[derive(Debug, Parser)]
+pub enum OtpSubCommand {
+    #[command(name = "generate", about = "Print the current TOTP or an HOTP code")]
+    Generate(OtpGenerateOpts),
+    #[command(name = "verify", about = "Verify a TOTP or HOTP code")]
+    Verify(OtpVerifyOpts),
+    #[command(
+        name = "new",
+        about = "Create a random secret and print its otpauth:// URI"
+    )]
+    New(OtpNewOpts),
+}
+
+/// Code parameters shared by all otp commands.
+#[derive(Debug, Args)]
+pub struct OtpParams {
+    #[arg(long, default_value_t = 6, value_parser = clap::value_parser!(u32).range(6..=8))]
+    pub digits: u32,
+    /// Time step in seconds
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+    pub period: u64,
+    #[arg(long, value_parser = parse_otp_algorithm, default_value = "sha1")]
+    pub algo: OtpAlgorithm,
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpGenerateOpts {
+    /// Base32 shared secret
+    #[arg(long)]
+    pub secret: String,
+    /// Use HOTP with this counter instead of the current time
+    #[arg(long)]
+    pub counter: Option<u64>,
+    #[command(flatten)]
+    pub params: OtpParams,
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpVerifyOpts {
+    /// Base32 shared secret
+    #[arg(long)]
+    pub secret: String,
+    #[arg(long)]
+    pub code: String,
+    /// Also accept codes this many steps before or after, at most 10
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(0..=10))]
+    pub window: u64,
+    /// Use HOTP with this counter instead of the current time
+    #[arg(long)]
+    pub counter: Option<u64>,
+    #[command(flatten)]
+    pub params: OtpParams,
+}
+
+#[derive(Debug, Parser)]
+pub struct OtpNewOpts {
+    /// Account name shown in the authenticator app, e.g. alice@example.com
+    #[arg(long)]
+    pub account: String,
+    #[arg(long, default_value = "rstool")]
+    pub issuer: String,
+    #[command(flatten)]
+    pub params: OtpParams,
+    /// Seed the generator for reproducible secrets, for testing only
+    #[arg(long, hide = true)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+fn parse_otp_algorithm(s: &str) -> Result<OtpAlgorithm, anyhow::Error> {
+    s.parse()
+}
+
+impl FromStr for OtpAlgorithm {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sha1" => Ok(OtpAlgorithm::Sha1),
+            "sha256" => Ok(OtpAlgorithm::Sha256),
+            "sha512" => Ok(OtpAlgorithm::Sha512),
+            _ => Err(anyhow::anyhow!("Invalid algorithm: {}", s)),
+        }
+    }
+}
+
+impl From<OtpAlgorithm> for &'static str {
+    fn from(algo: OtpAlgorithm) -> Self {
+        match algo {
+            OtpAlgorithm::Sha1 => "sha1",
+            OtpAlgorithm::Sha256 => "sha256",
+            OtpAlgorithm::Sha512 => "sha512",
+        }
+    }
+}
+
+impl fmt::Display for OtpAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
@@ -54,6 +54,10 @@ pub struct KeyGenerateOpts {
 
     #[arg(short, long, value_parser = verify_path)]
     pub output_path: PathBuf,
+
+    /// Seed the generator for reproducible keys, for testing only
+    #[arg(long, hide = true)]
+    pub seed: Option<u64>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -0,0 +1,68 @@
+use clap::Parser;
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Parser)]
+pub struct TokenOpts {
+    #[arg(long, value_parser = parse_token_kind, default_value = "hex")]
+    pub kind: TokenKind,
+    /// Random bytes in hex and b64url tokens
+    #[arg(long, default_value_t = 32, value_parser = clap::value_parser!(u16).range(1..=1024))]
+    pub bytes: u16,
+    /// Characters in a nanoid
+    #[arg(long, default_value_t = 21, value_parser = clap::value_parser!(u16).range(1..=1024))]
+    pub length: u16,
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub count: u32,
+    /// Seed the generator for reproducible output, for testing only
+    #[arg(long, hide = true)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TokenKind {
+    Hex,
+    B64Url,
+    Uuid4,
+    Uuid7,
+    Ulid,
+    Nanoid,
+}
+
+fn parse_token_kind(s: &str) -> Result<TokenKind, anyhow::Error> {
+    s.parse()
+}
+
+impl FromStr for TokenKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hex" => Ok(TokenKind::Hex),
+            "b64url" => Ok(TokenKind::B64Url),
+            "uuid4" => Ok(TokenKind::Uuid4),
+            "uuid7" => Ok(TokenKind::Uuid7),
+            "ulid" => Ok(TokenKind::Ulid),
+            "nanoid" => Ok(TokenKind::Nanoid),
+            _ => Err(anyhow::anyhow!("Invalid token kind: {}", s)),
+        }
+    }
+}
+
+impl From<TokenKind> for &'static str {
+    fn from(kind: TokenKind) -> Self {
+        match kind {
+            TokenKind::Hex => "hex",
+            TokenKind::B64Url => "b64url",
+            TokenKind::Uuid4 => "uuid4",
+            TokenKind::Uuid7 => "uuid7",
+            TokenKind::Ulid => "ulid",
+            TokenKind::Nanoid => "nanoid",
+        }
+    }
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
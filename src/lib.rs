@@ -3,14 +3,56 @@ mod process;
 mod utils;
 
 pub use cli::{
-    Base64Format, Base64SubCommand, Opts, OutputFormat, SubCommand, TextSignFormat, TextSubCommand,
+    Base64Format, Base64SubCommand, CsvSubCommand, GenPassFormat, GenPassPolicyOpts,
+    GenPassSubCommand, HashAlgorithm, HashPwSubCommand, Opts, OtpAlgorithm, OtpParams,
+    OtpSubCommand, OutputFormat, ReportFormat, SiteKdf, SubCommand, TextSignFormat, TextSubCommand,
+    TokenKind,
 };
+pub use process::GenPassConfig;
+pub use process::GeneratedPassword;
+pub use process::HashPwOptions;
+pub use process::HibpList;
+pub use process::OtpOptions;
+pub use process::PasswordPolicy;
+pub use process::StrengthReport;
+pub use process::TokenClock;
+pub use process::XmlOptions;
+pub use process::csv_to_json;
+pub use process::csv_to_json_parallel;
+pub use process::load_policy;
 pub use process::process_csv;
+pub use process::process_csv_decode;
+pub use process::process_csv_fake;
+pub use process::process_csv_melt;
+pub use process::process_csv_parallel;
+pub use process::process_csv_pivot;
+pub use process::process_csv_render;
+pub use process::process_csv_render_files;
 pub use process::process_decode;
 pub use process::process_encode;
 pub use process::process_genpass;
+pub use process::process_genpass_check;
+pub use process::process_genpass_config;
+pub use process::process_genpass_output;
+pub use process::process_genpass_pattern;
+pub use process::process_genpass_pronounceable;
+pub use process::process_hashpw;
+pub use process::process_hashpw_verify;
+pub use process::process_hibp_reject;
+pub use process::process_min_score;
+pub use process::process_otp_generate;
+pub use process::process_otp_new;
+pub use process::process_otp_verify;
+pub use process::process_passphrase;
+pub use process::process_policy_enforce;
+pub use process::process_site_password;
+pub use process::process_strength;
 pub use process::process_text_generate;
 pub use process::process_text_sign;
 pub use process::process_text_verify;
+pub use process::process_token;
 pub use utils::get_content;
 pub use utils::get_reader;
+pub use utils::get_rng;
+pub use utils::get_secret;
+pub use utils::get_writer;
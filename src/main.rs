@@ -1,49 +1,237 @@
 use std::fs;
+use std::io::Write;
 
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
 use clap::Parser;
 use rstool::{
-    Base64SubCommand, Opts, SubCommand, TextSubCommand, get_content, get_reader, process_csv,
-    process_decode, process_encode, process_genpass, process_text_generate, process_text_sign,
-    process_text_verify,
+    Base64SubCommand, CsvSubCommand, GenPassConfig, GenPassFormat, GenPassPolicyOpts,
+    GenPassSubCommand, GeneratedPassword, HashPwOptions, HashPwSubCommand, HibpList, Opts,
+    OtpOptions, OtpParams, OtpSubCommand, PasswordPolicy, ReportFormat, SubCommand, TextSubCommand,
+    TokenClock, XmlOptions, get_content, get_reader, get_rng, get_secret, get_writer, load_policy,
+    process_csv, process_csv_decode, process_csv_fake, process_csv_melt, process_csv_parallel,
+    process_csv_pivot, process_csv_render, process_csv_render_files, process_decode,
+    process_encode, process_genpass_check, process_genpass_config, process_genpass_output,
+    process_genpass_pattern, process_genpass_pronounceable, process_hashpw, process_hashpw_verify,
+    process_hibp_reject, process_min_score, process_otp_generate, process_otp_new,
+    process_otp_verify, process_passphrase, process_policy_enforce, process_site_password,
+    process_strength, process_text_generate, process_text_sign, process_text_verify, process_token,
 };
-use zxcvbn::zxcvbn;
 
 fn main() -> anyhow::Result<()> {
     let opts = Opts::parse();
 
     match opts.cmd {
-        SubCommand::Csv(opts) => {
-            let output = if let Some(output) = opts.output {
-                output.clone()
-            } else {
-                format!("output.{}", opts.format)
-            };
-            process_csv(&opts.input, output, opts.format)?;
-        }
+        SubCommand::Csv(opts) => match opts.cmd {
+            Some(CsvSubCommand::Pivot(opts)) => {
+                let mut reader = get_reader(&opts.input)?;
+                let mut writer = get_writer(&opts.output)?;
+                process_csv_pivot(
+                    &mut reader,
+                    &mut writer,
+                    &opts.index,
+                    &opts.columns,
+                    &opts.values,
+                )?;
+            }
+            Some(CsvSubCommand::Melt(opts)) => {
+                let mut reader = get_reader(&opts.input)?;
+                let mut writer = get_writer(&opts.output)?;
+                process_csv_melt(
+                    &mut reader,
+                    &mut writer,
+                    &opts.index,
+                    &opts.value_vars,
+                    &opts.var_name,
+                    &opts.value_name,
+                )?;
+            }
+            Some(CsvSubCommand::Render(opts)) => {
+                let template = String::from_utf8(get_content(&opts.template)?)?;
+                if let Some(pattern) = opts.output_pattern {
+                    let files = process_csv_render_files(
+                        &opts.input,
+                        opts.sheet.as_deref(),
+                        &template,
+                        &pattern,
+                    )?;
+                    eprintln!("Rendered {} files", files.len());
+                } else {
+                    let mut writer = get_writer(&opts.output)?;
+                    process_csv_render(&opts.input, opts.sheet.as_deref(), &template, &mut writer)?;
+                }
+            }
+            Some(CsvSubCommand::Fake(opts)) => {
+                let schema = String::from_utf8(get_content(&opts.schema)?)?;
+                let mut writer = get_writer(&opts.output)?;
+                process_csv_fake(&schema, opts.rows, opts.seed, &mut writer)?;
+            }
+            Some(CsvSubCommand::Decode(opts)) => {
+                let mut reader = get_reader(&opts.input)?;
+                let ret = process_csv_decode(&mut reader, opts.format)?;
+                let mut writer = get_writer(&opts.output)?;
+                writeln!(writer, "{ret}")?;
+            }
+            None => {
+                let input = opts
+                    .input
+                    .expect("clap requires --input without a subcommand");
+                let output = if let Some(output) = opts.output {
+                    output.clone()
+                } else {
+                    format!("output.{}", opts.format)
+                };
+                let xml = XmlOptions {
+                    root: opts.root,
+                    row: opts.row,
+                    attributes: opts.xml_attributes,
+                };
+                if opts.parallel {
+                    process_csv_parallel(
+                        &input,
+                        opts.sheet.as_deref(),
+                        output,
+                        opts.format,
+                        &xml,
+                        opts.jobs.unwrap_or(0),
+                    )?;
+                } else {
+                    process_csv(&input, opts.sheet.as_deref(), output, opts.format, &xml)?;
+                }
+            }
+        },
         SubCommand::GenPass(opts) => {
-            let ret = process_genpass(
-                opts.length,
-                opts.uppercase,
-                opts.lowercase,
-                opts.numbers,
-                opts.symbols,
-            )?;
-            println!("{ret}");
+            if let Some(GenPassSubCommand::Site(opts)) = opts.cmd {
+                let master = get_secret(&opts.master)?;
+                let ret = process_site_password(
+                    &GenPassConfig::from(&opts.rules),
+                    &master,
+                    &opts.site,
+                    &opts.login,
+                    opts.counter,
+                    opts.kdf,
+                )?;
+                println!("{ret}");
+                return Ok(());
+            }
+            if let Some(GenPassSubCommand::Check(opts)) = opts.cmd {
+                let user_inputs: Vec<&str> = opts.user_inputs.iter().map(String::as_str).collect();
+                let mut reader = get_reader(&opts.input)?;
+                let mut writer = get_writer(&opts.output)?;
+                let mut hibp = opts.hibp.as_deref().map(HibpList::open).transpose()?;
+                let policy = genpass_policy(&opts.policy)?;
+                process_genpass_check(
+                    &mut reader,
+                    &mut writer,
+                    &user_inputs,
+                    opts.show,
+                    hibp.as_mut(),
+                    policy.as_ref(),
+                )?;
+                return Ok(());
+            }
+            let wordlist = match &opts.wordlist {
+                Some(path) => Some(String::from_utf8(get_content(path)?)?),
+                None => None,
+            };
+            let user_inputs: Vec<&str> = opts.user_inputs.iter().map(String::as_str).collect();
+
+            let policy = genpass_policy(&opts.policy)?;
+            let config = match &policy {
+                Some(policy) => policy.config(),
+                None => GenPassConfig::from(&opts.rules),
+            };
+            let mut rng = get_rng(opts.seed);
+            let mut generate = || -> anyhow::Result<(String, f64)> {
+                if let Some(pattern) = &opts.pattern {
+                    process_genpass_pattern(
+                        pattern,
+                        opts.rules.symbols_set.as_deref(),
+                        &opts.rules.exclude,
+                        &mut rng,
+                    )
+                } else if opts.passphrase {
+                    process_passphrase(
+                        opts.words,
+                        &opts.separator,
+                        opts.capitalize,
+                        opts.append_digit,
+                        wordlist.as_deref(),
+                        &mut rng,
+                    )
+                } else if opts.pronounceable {
+                    process_genpass_pronounceable(&config, &mut rng)
+                } else {
+                    Ok((
+                        process_genpass_config(&config, &mut rng)?,
+                        config.entropy()?,
+                    ))
+                }
+            };
+
+            let min_score = opts.min_score.unwrap_or(0);
+            let mut hibp = opts.hibp.as_deref().map(HibpList::open).transpose()?;
+            let mut passwords = Vec::new();
+            let mut reports = Vec::new();
+            for _ in 0..opts.count {
+                let mut entropy = 0.0;
+                let mut candidate = || {
+                    let (ret, bits) = generate()?;
+                    entropy = bits;
+                    Ok(ret)
+                };
+                let mut compliant = || match &policy {
+                    Some(policy) => process_policy_enforce(&mut candidate, policy),
+                    None => candidate(),
+                };
+                let password = match hibp.as_mut() {
+                    Some(list) => process_min_score(
+                        || process_hibp_reject(&mut compliant, list),
+                        min_score,
+                        &user_inputs,
+                    )?,
+                    None => process_min_score(compliant, min_score, &user_inputs)?,
+                };
+
+                let report = process_strength(&password, &user_inputs);
+                passwords.push(GeneratedPassword {
+                    password,
+                    entropy,
+                    score: report.score,
+                });
+                reports.push(report);
+            }
+            process_genpass_output(&passwords, opts.format, &mut std::io::stdout())?;
 
-            let estimate = zxcvbn(&ret, &[]);
-            eprintln!("Password strength: {}", estimate.score());
+            // json and csv carry entropy and score, text keeps them on stderr
+            let text = matches!(opts.format, GenPassFormat::Text);
+            if text && let [generated] = passwords.as_slice() {
+                eprintln!("Entropy: {:.1} bits", generated.entropy);
+            }
+            // the full report goes to stderr whatever the output format
+            for report in &reports {
+                match opts.report {
+                    Some(ReportFormat::Text) => eprint!("{report}"),
+                    Some(ReportFormat::Json) => {
+                        eprintln!("{}", serde_json::to_string_pretty(report)?)
+                    }
+                    None if text && opts.count == 1 => {
+                        eprintln!("Password strength: {}", report.score)
+                    }
+                    None => {}
+                }
+            }
         }
         SubCommand::Base64(subcmd) => match subcmd {
             Base64SubCommand::Encode(opts) => {
                 let mut reader = get_reader(&opts.input)?;
-                let ret = process_encode(&mut reader, opts.format)?;
-                println!("{ret}");
+                let mut stdout = std::io::stdout();
+                process_encode(&mut reader, &mut stdout, opts.format)?;
+                writeln!(stdout)?;
             }
             Base64SubCommand::Decode(opts) => {
                 let mut reader = get_reader(&opts.input)?;
-                let ret = process_decode(&mut reader, opts.format)?;
-                println!("{ret}");
+                let mut writer = get_writer(&opts.output)?;
+                process_decode(&mut reader, &mut writer, opts.format)?;
             }
         },
         SubCommand::Text(subcmd) => match subcmd {
@@ -67,17 +255,116 @@ fn main() -> anyhow::Result<()> {
                 }
             }
             TextSubCommand::Generate(opts) => {
-                let key = process_text_generate(opts.format)?;
+                let key = process_text_generate(opts.format, &mut get_rng(opts.seed))?;
                 for (k, v) in key {
                     fs::write(opts.output_path.join(k), v)?;
                 }
             }
         },
+        SubCommand::Token(opts) => {
+            let mut rng = get_rng(opts.seed);
+            let mut clock = TokenClock::default();
+            for _ in 0..opts.count {
+                let ret = process_token(
+                    opts.kind,
+                    opts.bytes as usize,
+                    opts.length as usize,
+                    &mut clock,
+                    &mut rng,
+                )?;
+                println!("{ret}");
+            }
+        }
+        SubCommand::HashPw(opts) => {
+            if let Some(HashPwSubCommand::Verify(opts)) = opts.cmd {
+                let password = get_secret(&opts.input)?;
+                if process_hashpw_verify(&password, &opts.hash)? {
+                    println!("✓ Password verified");
+                } else {
+                    println!("⚠ Password not verified");
+                }
+                return Ok(());
+            }
+            let mut rng = get_rng(opts.seed);
+            let password = if opts.generate {
+                let config = GenPassConfig {
+                    length: opts.length,
+                    ..Default::default()
+                };
+                process_genpass_config(&config, &mut rng)?.into_bytes()
+            } else {
+                get_secret(&opts.input)?
+            };
+            let hash_opts = HashPwOptions {
+                algo: opts.algo,
+                m_cost: opts.m,
+                t_cost: opts.t,
+                p_cost: opts.p,
+                cost: opts.cost,
+                log_n: opts.log_n,
+                r: opts.r,
+                rounds: opts.rounds,
+            };
+            let hash = process_hashpw(&password, &hash_opts, &mut rng)?;
+            // only show a generated password once it has a hash
+            if opts.generate {
+                eprintln!("Password: {}", String::from_utf8(password)?);
+            }
+            println!("{hash}");
+        }
+        SubCommand::Otp(subcmd) => match subcmd {
+            OtpSubCommand::Generate(opts) => {
+                let ret =
+                    process_otp_generate(&opts.secret, opts.counter, &otp_options(&opts.params))?;
+                println!("{ret}");
+            }
+            OtpSubCommand::Verify(opts) => {
+                let verified = process_otp_verify(
+                    &opts.secret,
+                    &opts.code,
+                    opts.window,
+                    opts.counter,
+                    &otp_options(&opts.params),
+                )?;
+                if verified {
+                    println!("✓ Code verified");
+                } else {
+                    println!("⚠ Code not verified");
+                }
+            }
+            OtpSubCommand::New(opts) => {
+                let (secret, uri) = process_otp_new(
+                    &opts.account,
+                    &opts.issuer,
+                    &otp_options(&opts.params),
+                    &mut get_rng(opts.seed),
+                )?;
+                eprintln!("Secret: {secret}");
+                println!("{uri}");
+            }
+        },
     }
 
     Ok(())
 }
 
+fn genpass_policy(opts: &GenPassPolicyOpts) -> anyhow::Result<Option<PasswordPolicy>> {
+    let Some(name) = &opts.policy else {
+        return Ok(None);
+    };
+    let content = String::from_utf8(get_content(&opts.policy_file)?)?;
+    let toml = opts.policy_file.ends_with(".toml");
+    Ok(Some(load_policy(&content, toml, name)?))
+}
+
+fn otp_options(params: &OtpParams) -> OtpOptions {
+    OtpOptions {
+        digits: params.digits,
+        period: params.period,
+        algo: params.algo,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
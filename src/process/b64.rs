@@ -1,35 +1,137 @@
 use crate::Base64Format;
-use anyhow::Result;
-use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use anyhow::{Result, bail};
+use base64::alphabet;
+use base64::engine::general_purpose::{
+    NO_PAD, STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD,
+};
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
 use base64::prelude::*;
-use std::io::Read;
-
-pub fn process_encode(reader: &mut dyn Read, format: Base64Format) -> Result<String> {
-    let mut buf = Vec::new();
-    reader.read_to_end(&mut buf)?;
-    let encoded = match format {
-        Base64Format::Standard => STANDARD.encode(&buf),
-        Base64Format::UrlSafe => URL_SAFE_NO_PAD.encode(&buf),
+use base64::read::DecoderReader;
+use base64::write::EncoderWriter;
+use std::io::{self, Read, Write};
+
+/// Chunk size for skipping whitespace, memory use doesn't grow with the input.
+const BUFFER_SIZE: usize = 8 * 1024;
+
+const BCRYPT: GeneralPurpose = GeneralPurpose::new(&alphabet::BCRYPT, NO_PAD);
+const CRYPT: GeneralPurpose = GeneralPurpose::new(&alphabet::CRYPT, NO_PAD);
+/// Decodes `auto` input once URL-safe characters are mapped to standard ones.
+const AUTO: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Stream `reader` to `writer` as base64, without a trailing newline.
+pub fn process_encode(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    format: Base64Format,
+) -> Result<()> {
+    if matches!(format, Base64Format::Auto) {
+        bail!("Format auto only applies to decoding, pick an alphabet to encode with");
+    }
+    let mut encoder = EncoderWriter::new(writer, engine(format));
+    io::copy(reader, &mut encoder)?;
+    encoder.finish()?.flush()?;
+    Ok(())
+}
+
+pub(crate) fn encode_bytes(buf: &[u8], format: Base64Format) -> String {
+    engine(format).encode(buf)
+}
+
+/// Stream base64 from `reader` to `writer` as raw bytes, which need not be
+/// valid UTF-8. Whitespace and line breaks anywhere in the input are skipped.
+///
+/// `auto` accepts the standard and URL-safe alphabets with or without padding.
+/// bcrypt and crypt use the same characters in a different order, so they have
+/// to be named.
+pub fn process_decode(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    format: Base64Format,
+) -> Result<()> {
+    let mut input = Sanitize {
+        inner: reader,
+        buf: vec![0; BUFFER_SIZE],
+        auto: matches!(format, Base64Format::Auto),
+        url_safe: None,
     };
-    Ok(encoded)
+    let mut decoder = DecoderReader::new(&mut input, engine(format));
+    io::copy(&mut decoder, writer)?;
+    writer.flush()?;
+    Ok(())
 }
 
-pub fn process_decode(reader: &mut dyn Read, format: Base64Format) -> Result<String> {
-    let mut buf = String::new();
-    reader.read_to_string(&mut buf)?;
-    // avoid accidental newlines
-    let buf = buf.trim();
+fn engine(format: Base64Format) -> &'static GeneralPurpose {
+    match format {
+        Base64Format::Standard => &STANDARD,
+        Base64Format::StandardNoPad => &STANDARD_NO_PAD,
+        Base64Format::UrlSafe => &URL_SAFE_NO_PAD,
+        Base64Format::UrlSafePad => &URL_SAFE,
+        Base64Format::Bcrypt => &BCRYPT,
+        Base64Format::Crypt => &CRYPT,
+        Base64Format::Auto => &AUTO,
+    }
+}
 
-    println!("buf: {buf}");
+/// A reader that drops ASCII whitespace, which the base64 decoder rejects. For
+/// `auto` it also maps the URL-safe alphabet onto the standard one.
+struct Sanitize<'a> {
+    inner: &'a mut dyn Read,
+    buf: Vec<u8>,
+    auto: bool,
+    /// Alphabet seen so far, mixing both is an error rather than a guess
+    url_safe: Option<bool>,
+}
 
-    let decoded = match format {
-        Base64Format::Standard => BASE64_STANDARD.decode(buf)?,
-        Base64Format::UrlSafe => URL_SAFE_NO_PAD.decode(buf)?,
-    };
+impl Sanitize<'_> {
+    fn translate(&mut self, b: u8) -> io::Result<u8> {
+        let (url_safe, standard) = match b {
+            b'+' | b'/' => (false, b),
+            b'-' => (true, b'+'),
+            b'_' => (true, b'/'),
+            b'.' => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "input looks like bcrypt or crypt base64, pass --format bcrypt or crypt",
+                ));
+            }
+            _ => return Ok(b),
+        };
+        if *self.url_safe.get_or_insert(url_safe) != url_safe {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "input mixes the standard and URL-safe alphabets",
+            ));
+        }
+        Ok(standard)
+    }
+}
 
-    // TODO: decoded data might not be string(but for this example, we assume it is)
-    let decoded = String::from_utf8(decoded)?;
-    Ok(decoded)
+impl Read for Sanitize<'_> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let want = out.len().min(self.buf.len());
+        loop {
+            let n = self.inner.read(&mut self.buf[..want])?;
+            if n == 0 {
+                return Ok(0);
+            }
+            let mut len = 0;
+            for i in 0..n {
+                let b = self.buf[i];
+                if b.is_ascii_whitespace() {
+                    continue;
+                }
+                out[len] = if self.auto { self.translate(b)? } else { b };
+                len += 1;
+            }
+            // a chunk of only whitespace isn't the end of the input
+            if len > 0 {
+                return Ok(len);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -42,7 +144,12 @@ mod tests {
         let input = "Cargo.toml";
         let mut reader = get_reader(input)?;
         let format = Base64Format::Standard;
-        assert!(process_encode(&mut reader, format).is_ok());
+        let mut output = Vec::new();
+        process_encode(&mut reader, &mut output, format)?;
+        assert_eq!(
+            output,
+            encode_bytes(&std::fs::read(input)?, format).as_bytes()
+        );
         Ok(())
     }
 
@@ -51,7 +158,135 @@ mod tests {
         let input = "fixtures/b64.txt";
         let mut reader = get_reader(input)?;
         let format = Base64Format::UrlSafe;
-        process_decode(&mut reader, format)?;
+        process_decode(&mut reader, &mut io::sink(), format)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_decode_binary() -> Result<()> {
+        let data: Vec<u8> = (0..=255).collect();
+        for format in [Base64Format::Standard, Base64Format::UrlSafe] {
+            let encoded = encode_bytes(&data, format) + "\n";
+            let mut output = Vec::new();
+            process_decode(&mut encoded.as_bytes(), &mut output, format)?;
+            assert_eq!(output, data);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_base64_formats() -> Result<()> {
+        // expected values made with Python's base64 and a translated alphabet
+        let data = b"\xfb\xff\xbf\x00\x10hi";
+        let formats = [
+            (Base64Format::Standard, "+/+/ABBoaQ=="),
+            (Base64Format::StandardNoPad, "+/+/ABBoaQ"),
+            (Base64Format::UrlSafe, "-_-_ABBoaQ"),
+            (Base64Format::UrlSafePad, "-_-_ABBoaQ=="),
+            (Base64Format::Bcrypt, "8989.//mYO"),
+            (Base64Format::Crypt, "yzyz.//cOE"),
+        ];
+        for (format, expected) in formats {
+            let mut output = Vec::new();
+            process_encode(&mut &data[..], &mut output, format)?;
+            assert_eq!(String::from_utf8(output)?, expected, "{format}");
+
+            // pasted from an email: wrapped, indented, CRLF
+            let pasted = format!(" {}\r\n\t{} \r\n", &expected[..5], &expected[5..]);
+            let mut output = Vec::new();
+            process_decode(&mut pasted.as_bytes(), &mut output, format)?;
+            assert_eq!(output, data, "{format}");
+        }
+        assert!(process_encode(&mut &data[..], &mut io::sink(), Base64Format::Auto).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_decode_auto() -> Result<()> {
+        let data = b"\xfb\xff\xbf\x00\x10hi";
+        for input in [
+            "+/+/ABBoaQ==",
+            "+/+/ABBoaQ",
+            "-_-_ABBoaQ",
+            "-_-\n_ABB\noaQ==\n",
+        ] {
+            let mut output = Vec::new();
+            process_decode(&mut input.as_bytes(), &mut output, Base64Format::Auto)?;
+            assert_eq!(output, data, "{input}");
+        }
+        for input in ["+_+_ABBoaQ", "8989.//mYO", "+/+/ABBoaQ=x"] {
+            let ret = process_decode(&mut input.as_bytes(), &mut io::sink(), Base64Format::Auto);
+            assert!(ret.is_err(), "{input}");
+        }
+        Ok(())
+    }
+
+    /// Repeats `pattern` up to `len` bytes without holding them in memory.
+    struct Cycle {
+        pattern: &'static [u8],
+        pos: usize,
+        len: usize,
+    }
+
+    impl Read for Cycle {
+        fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+            let n = out.len().min(self.len - self.pos);
+            for (i, b) in out[..n].iter_mut().enumerate() {
+                *b = self.pattern[(self.pos + i) % self.pattern.len()];
+            }
+            self.pos += n;
+            Ok(n)
+        }
+    }
+
+    /// Checks every byte written against `pattern` and only keeps a count.
+    struct Verify {
+        pattern: &'static [u8],
+        pos: usize,
+    }
+
+    impl Write for Verify {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            for &b in buf {
+                assert_eq!(b, self.pattern[self.pos % self.pattern.len()]);
+                self.pos += 1;
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_process_base64_streams_large_input() -> Result<()> {
+        // 12 MiB of input, far more than any buffer involved
+        const LEN: usize = 12 << 20;
+        let mut reader = Cycle {
+            pattern: b"\xfb\xff\xbf",
+            pos: 0,
+            len: LEN,
+        };
+        let mut writer = Verify {
+            pattern: b"+/+/",
+            pos: 0,
+        };
+        process_encode(&mut reader, &mut writer, Base64Format::Standard)?;
+        assert_eq!(writer.pos, LEN / 3 * 4);
+
+        let mut reader = Cycle {
+            pattern:
+                b"-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_-_\r\n",
+            pos: 0,
+            len: LEN / 3 * 4 / 76 * 78,
+        };
+        let mut writer = Verify {
+            pattern: b"\xfb\xff\xbf",
+            pos: 0,
+        };
+        process_decode(&mut reader, &mut writer, Base64Format::UrlSafe)?;
+        assert_eq!(writer.pos, LEN / 3 * 4 / 76 * 57);
         Ok(())
     }
 }
@@ -0,0 +1,155 @@
+use super::hibp::HibpList;
+use super::policy::PasswordPolicy;
+use super::strength::process_strength;
+use anyhow::Result;
+use std::io::{BufRead, BufReader, Read, Write};
+
+/// Width of the longest bar in the summary histogram.
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// Stands in for every hidden password, so a shared report leaks neither its
+/// characters nor its length. Entries are told apart by their line number.
+const MASK: &str = "********";
+
+/// Score one password per line of `reader` and write a report to `writer`.
+///
+/// Empty lines are skipped. Passwords are masked unless `show` is set. With a
+/// breach list each password is also looked up there, with a policy its
+/// violations are listed. Returns the number of passwords per zxcvbn score (0-4).
+pub fn process_genpass_check(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    user_inputs: &[&str],
+    show: bool,
+    mut hibp: Option<&mut HibpList>,
+    policy: Option<&PasswordPolicy>,
+) -> Result<[usize; 5]> {
+    let mut histogram = [0; 5];
+    let mut breached = 0;
+    let mut noncompliant = 0;
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        let password = line.strip_suffix('\r').unwrap_or(&line);
+        if password.is_empty() {
+            continue;
+        }
+
+        let report = process_strength(password, user_inputs);
+        histogram[report.score as usize] += 1;
+
+        let shown = if show { password } else { MASK };
+        writeln!(
+            writer,
+            "line {}: {} score {}/4, 10^{:.1} guesses",
+            i + 1,
+            shown,
+            report.score,
+            report.guesses_log10
+        )?;
+        if let Some(list) = hibp.as_deref_mut() {
+            let count = list.lookup(password)?;
+            if count > 0 {
+                breached += 1;
+                writeln!(writer, "    breached: seen {count} times")?;
+            }
+        }
+        if let Some(policy) = policy {
+            let violations = policy.violations(password);
+            if !violations.is_empty() {
+                noncompliant += 1;
+            }
+            for violation in violations {
+                writeln!(writer, "    policy: {violation}")?;
+            }
+        }
+        if let Some(warning) = &report.warning {
+            writeln!(writer, "    warning: {warning}")?;
+        }
+        for suggestion in &report.suggestions {
+            writeln!(writer, "    suggestion: {suggestion}")?;
+        }
+    }
+
+    write_histogram(writer, &histogram)?;
+    if hibp.is_some() {
+        writeln!(writer, "Found in breach list: {breached}")?;
+    }
+    if policy.is_some() {
+        writeln!(writer, "Policy violations: {noncompliant}")?;
+    }
+    Ok(histogram)
+}
+
+fn write_histogram(writer: &mut dyn Write, histogram: &[usize; 5]) -> Result<()> {
+    let total: usize = histogram.iter().sum();
+    let max = histogram.iter().copied().max().unwrap_or(0).max(1);
+    writeln!(writer)?;
+    writeln!(writer, "Checked {total} passwords")?;
+    for (score, &count) in histogram.iter().enumerate() {
+        let bar = "#".repeat((count * HISTOGRAM_WIDTH).div_ceil(max));
+        writeln!(writer, "  score {score}: {bar:<HISTOGRAM_WIDTH$} {count}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::load_policy;
+
+    #[test]
+    fn test_process_genpass_check() -> Result<()> {
+        let input = "password\r\n\nqwerty\nU&o2JNaNyaqF\n";
+        let mut output = Vec::new();
+        let histogram =
+            process_genpass_check(&mut input.as_bytes(), &mut output, &[], false, None, None)?;
+        assert_eq!(histogram.iter().sum::<usize>(), 3);
+        assert_eq!(histogram[0], 2);
+        assert_eq!(histogram[4], 1);
+
+        let output = String::from_utf8(output)?;
+        assert!(output.contains("line 1: ******** score 0/4"));
+        assert!(output.contains("line 4: ******** score 4/4"));
+        assert!(!output.contains("qwerty"));
+        assert!(!output.contains("breach"));
+        assert!(!output.contains("policy"));
+        assert!(output.contains("warning: "));
+        assert!(output.contains("Checked 3 passwords"));
+
+        let mut output = Vec::new();
+        let mut list = HibpList::open("fixtures/hibp_sample.txt")?;
+        process_genpass_check(
+            &mut input.as_bytes(),
+            &mut output,
+            &[],
+            true,
+            Some(&mut list),
+            None,
+        )?;
+        let output = String::from_utf8(output)?;
+        assert!(output.contains("line 3: qwerty score 0/4"));
+        assert!(output.contains("breached: seen 32095 times"));
+        assert!(output.contains("Found in breach list: 2"));
+
+        let mut output = Vec::new();
+        let policy = load_policy(
+            include_str!("../../fixtures/policies.yaml"),
+            false,
+            "corp-ad",
+        )?;
+        process_genpass_check(
+            &mut input.as_bytes(),
+            &mut output,
+            &[],
+            false,
+            None,
+            Some(&policy),
+        )?;
+        let output = String::from_utf8(output)?;
+        assert!(output.contains("    policy: length is outside 14..=64"));
+        assert!(output.contains("    policy: needs at least 1 uppercase"));
+        assert!(!output.contains("length 8"));
+        assert!(output.contains("Policy violations: 3"));
+        Ok(())
+    }
+}
@@ -1,10 +1,20 @@
+use super::excel::{is_excel, read_excel, to_xlsx};
+use super::xml::{XmlOptions, to_xml};
 use crate::cli::OutputFormat;
-use anyhow::Result;
-use csv::Reader;
+use crate::get_reader;
+use anyhow::{Result, bail};
+use csv::{Reader, StringRecord};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::fs;
+use std::io::{Cursor, Read};
+
+/// 并行模式下每个分块包含的记录数
+const CHUNK_SIZE: usize = 4096;
 
 /// 球员数据结构体
+#[allow(dead_code)]
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 struct Player {
@@ -19,28 +29,178 @@ struct Player {
 
 /// 将CSV文件转换为指定格式
 ///
+/// `input` 也可以是 Excel 工作簿，此时读取 `sheet` 指定的工作表（默认第一个）。
+/// `xml` 只在输出为 XML 时使用。
+///
 /// 关于迭代器操作的详细说明，请参考：docs/iterator_operations.md
-pub fn process_csv(input: &str, output: String, format: OutputFormat) -> Result<()> {
-    let mut reader = Reader::from_path(input)?;
+pub fn process_csv(
+    input: &str,
+    sheet: Option<&str>,
+    output: String,
+    format: OutputFormat,
+    xml: &XmlOptions,
+) -> Result<()> {
+    let reader = open_reader(input, sheet)?;
+    let ret = csv_to_json(reader)?;
+    write_output(&ret, output, format, xml)
+}
+
+/// 将CSV文件转换为指定格式，记录的转换在线程池中并行完成
+///
+/// `jobs` 为工作线程数，0 表示使用 CPU 核数。输出顺序与输入一致。
+pub fn process_csv_parallel(
+    input: &str,
+    sheet: Option<&str>,
+    output: String,
+    format: OutputFormat,
+    xml: &XmlOptions,
+    jobs: usize,
+) -> Result<()> {
+    let reader = open_reader(input, sheet)?;
+    let ret = csv_to_json_parallel(reader, jobs)?;
+    write_output(&ret, output, format, xml)
+}
+
+/// 逐条读取记录并转换为 `serde_json::Value`
+pub fn csv_to_json<R: Read>(mut reader: Reader<R>) -> Result<Vec<Value>> {
     let mut ret = Vec::with_capacity(128);
 
     let headers = reader.headers()?.clone();
     for result in reader.records() {
         let record = result?;
-        // 详细的迭代器操作说明请参考：docs/iterator_operations.md
-        let json_value: serde_json::Value = headers
-            .iter()
-            .zip(record.iter())
-            .map(|(k, v)| (k.to_string(), serde_json::Value::String(v.to_string())))
-            .collect();
-        ret.push(json_value);
+        ret.push(record_to_json(&headers, &record));
     }
+    Ok(ret)
+}
+
+/// 按记录边界将输入切分为多个分块，在线程池中并行转换后按原始顺序合并
+pub fn csv_to_json_parallel<R: Read>(mut reader: Reader<R>, jobs: usize) -> Result<Vec<Value>> {
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(jobs).build()?;
 
+    let headers = reader.headers()?.clone();
+    let mut chunks = Vec::new();
+    let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+    for result in reader.into_records() {
+        chunk.push(result?);
+        if chunk.len() == CHUNK_SIZE {
+            chunks.push(std::mem::replace(
+                &mut chunk,
+                Vec::with_capacity(CHUNK_SIZE),
+            ));
+        }
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+
+    // par_iter + collect 会保持分块的原始顺序
+    let converted: Vec<Vec<Value>> = pool.install(|| {
+        chunks
+            .par_iter()
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .map(|record| record_to_json(&headers, record))
+                    .collect()
+            })
+            .collect()
+    });
+
+    Ok(converted.into_iter().flatten().collect())
+}
+
+// 详细的迭代器操作说明请参考：docs/iterator_operations.md
+pub(crate) fn record_to_json(headers: &StringRecord, record: &StringRecord) -> Value {
+    headers
+        .iter()
+        .zip(record.iter())
+        .map(|(k, v)| (k.to_string(), Value::String(v.to_string())))
+        .collect()
+}
+
+/// 将 `process_csv` 生成的文件解码并格式化为 JSON，主要用于查看 MessagePack/CBOR 这类二进制格式
+pub fn process_csv_decode(reader: &mut dyn Read, format: OutputFormat) -> Result<String> {
+    let ret: Vec<Value> = match format {
+        OutputFormat::Json => serde_json::from_reader(reader)?,
+        OutputFormat::Yaml => serde_yaml::from_reader(reader)?,
+        OutputFormat::MsgPack => rmp_serde::from_read(reader)?,
+        OutputFormat::Cbor => ciborium::from_reader(reader)?,
+        OutputFormat::Xlsx | OutputFormat::Xml => {
+            bail!("Decoding {} is not supported", format)
+        }
+    };
+    Ok(serde_json::to_string_pretty(&ret)?)
+}
+
+pub(crate) fn open_reader(input: &str, sheet: Option<&str>) -> Result<Reader<Box<dyn Read>>> {
+    let reader: Box<dyn Read> = if is_excel(input) {
+        Box::new(Cursor::new(read_excel(input, sheet)?))
+    } else {
+        get_reader(input)?
+    };
+    Ok(Reader::from_reader(reader))
+}
+
+fn write_output(
+    ret: &[Value],
+    output: String,
+    format: OutputFormat,
+    xml: &XmlOptions,
+) -> Result<()> {
     let content = match format {
-        OutputFormat::Json => serde_json::to_string_pretty(&ret)?,
-        OutputFormat::Yaml => serde_yaml::to_string(&ret)?,
+        OutputFormat::Json => serde_json::to_string_pretty(ret)?.into_bytes(),
+        OutputFormat::Yaml => serde_yaml::to_string(ret)?.into_bytes(),
+        OutputFormat::Xlsx => to_xlsx(ret)?,
+        OutputFormat::Xml => to_xml(ret, xml)?.into_bytes(),
+        OutputFormat::MsgPack => rmp_serde::to_vec_named(ret)?,
+        OutputFormat::Cbor => {
+            let mut buf = Vec::new();
+            ciborium::into_writer(ret, &mut buf)?;
+            buf
+        }
     };
 
     fs::write(output, content)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_to_json_parallel_keeps_order() -> Result<()> {
+        let mut data = String::from("Id,Name\n");
+        for i in 0..(CHUNK_SIZE * 3 + 7) {
+            data.push_str(&format!("{i},name-{i}\n"));
+        }
+
+        let expected = csv_to_json(Reader::from_reader(data.as_bytes()))?;
+        let ret = csv_to_json_parallel(Reader::from_reader(data.as_bytes()), 4)?;
+        assert_eq!(ret.len(), CHUNK_SIZE * 3 + 7);
+        assert_eq!(ret, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_csv_decode_binary_formats() -> Result<()> {
+        let expected =
+            serde_json::to_string_pretty(&csv_to_json(Reader::from_path("assets/juventus.csv")?)?)?;
+
+        for format in [OutputFormat::MsgPack, OutputFormat::Cbor] {
+            let output = std::env::temp_dir().join(format!("rstool_test_decode.{format}"));
+            let output = output.to_str().expect("temp dir is valid utf-8");
+            process_csv(
+                "assets/juventus.csv",
+                None,
+                output.to_string(),
+                format,
+                &XmlOptions::default(),
+            )?;
+
+            let mut reader = fs::File::open(output)?;
+            assert_eq!(process_csv_decode(&mut reader, format)?, expected);
+        }
+        Ok(())
+    }
+}
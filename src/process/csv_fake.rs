@@ -0,0 +1,374 @@
+use crate::get_rng;
+use anyhow::{Result, anyhow, bail};
+use csv::Writer;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+use std::io::Write;
+
+const FIRST_NAMES: &[&str] = &[
+    "Aaron",
+    "Adrien",
+    "Alex",
+    "Blaise",
+    "Cristiano",
+    "Daniele",
+    "Douglas",
+    "Emre",
+    "Federico",
+    "Giorgio",
+    "Gonzalo",
+    "Juan",
+    "Leonardo",
+    "Mario",
+    "Matthijs",
+    "Mattia",
+    "Merih",
+    "Miralem",
+    "Moise",
+    "Paulo",
+    "Rodrigo",
+    "Sami",
+    "Wojciech",
+];
+const LAST_NAMES: &[&str] = &[
+    "Bentancur",
+    "Bernardeschi",
+    "Bonucci",
+    "Buffon",
+    "Can",
+    "Chiellini",
+    "Costa",
+    "Cuadrado",
+    "Danilo",
+    "De Ligt",
+    "Demiral",
+    "Dybala",
+    "Higuain",
+    "Kean",
+    "Khedira",
+    "Matuidi",
+    "Perin",
+    "Pjanic",
+    "Rabiot",
+    "Ramsey",
+    "Ronaldo",
+    "Rugani",
+    "Sandro",
+    "Szczesny",
+];
+const EMAIL_DOMAINS: &[&str] = &["example.com", "example.org", "example.net"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// 假数据的列定义，从 YAML 中读取
+#[derive(Debug, Deserialize)]
+struct FakeSchema {
+    columns: Vec<FakeColumn>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FakeColumn {
+    name: String,
+    #[serde(flatten)]
+    kind: FakeKind,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum FakeKind {
+    Name,
+    FirstName,
+    LastName,
+    Email,
+    Uuid,
+    Integer {
+        #[serde(default)]
+        min: i64,
+        #[serde(default = "default_max")]
+        max: i64,
+    },
+    Enum {
+        values: Vec<String>,
+    },
+    Date {
+        #[serde(default = "default_from")]
+        from: String,
+        #[serde(default = "default_to")]
+        to: String,
+        #[serde(default = "default_date_format")]
+        format: String,
+    },
+}
+
+/// 一个已经校验过参数、可以直接生成数据的列
+enum Generator<'a> {
+    Name,
+    FirstName,
+    LastName,
+    Email,
+    Uuid,
+    Integer(i64, i64),
+    Enum(&'a [String]),
+    Date(i64, i64, &'a str),
+}
+
+/// 根据 YAML schema 生成 `rows` 行假数据，以 CSV 写入 `writer`
+///
+/// 给定 `seed` 时输出可以复现。
+pub fn process_csv_fake(
+    schema: &str,
+    rows: usize,
+    seed: Option<u64>,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    let schema: FakeSchema = serde_yaml::from_str(schema)?;
+    if schema.columns.is_empty() {
+        bail!("Schema has no columns");
+    }
+    let generators = schema
+        .columns
+        .iter()
+        .map(|column| Generator::try_from(&column.kind))
+        .collect::<Result<Vec<_>>>()?;
+
+    // ChaCha20 is pinned, unlike StdRng, so seeded output stays the same across rand versions
+    let mut rng = get_rng(seed);
+
+    let mut writer = Writer::from_writer(writer);
+    writer.write_record(schema.columns.iter().map(|c| &c.name))?;
+    for _ in 0..rows {
+        let record: Vec<String> = generators.iter().map(|g| g.generate(&mut rng)).collect();
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+impl<'a> TryFrom<&'a FakeKind> for Generator<'a> {
+    type Error = anyhow::Error;
+
+    fn try_from(kind: &'a FakeKind) -> Result<Self> {
+        let generator = match kind {
+            FakeKind::Name => Generator::Name,
+            FakeKind::FirstName => Generator::FirstName,
+            FakeKind::LastName => Generator::LastName,
+            FakeKind::Email => Generator::Email,
+            FakeKind::Uuid => Generator::Uuid,
+            FakeKind::Integer { min, max } => {
+                if min > max {
+                    bail!("Invalid integer range: {}..={}", min, max);
+                }
+                Generator::Integer(*min, *max)
+            }
+            FakeKind::Enum { values } => {
+                if values.is_empty() {
+                    bail!("Enum column needs at least one value");
+                }
+                Generator::Enum(values)
+            }
+            FakeKind::Date { from, to, format } => {
+                let (from, to) = (parse_date(from)?, parse_date(to)?);
+                if from > to {
+                    bail!("Invalid date range");
+                }
+                Generator::Date(from, to, format)
+            }
+        };
+        Ok(generator)
+    }
+}
+
+impl Generator<'_> {
+    fn generate(&self, rng: &mut impl Rng) -> String {
+        match self {
+            Generator::Name => format!("{} {}", pick(FIRST_NAMES, rng), pick(LAST_NAMES, rng)),
+            Generator::FirstName => pick(FIRST_NAMES, rng).to_string(),
+            Generator::LastName => pick(LAST_NAMES, rng).to_string(),
+            Generator::Email => {
+                let first = pick(FIRST_NAMES, rng).to_lowercase();
+                let last = pick(LAST_NAMES, rng).to_lowercase().replace(' ', "");
+                let n: u16 = rng.gen_range(1..1000);
+                format!("{first}.{last}{n}@{}", pick(EMAIL_DOMAINS, rng))
+            }
+            Generator::Uuid => {
+                let mut bytes: [u8; 16] = rng.r#gen();
+                // version 4, variant RFC 4122
+                bytes[6] = (bytes[6] & 0x0f) | 0x40;
+                bytes[8] = (bytes[8] & 0x3f) | 0x80;
+                format_uuid(&bytes)
+            }
+            Generator::Integer(min, max) => rng.gen_range(*min..=*max).to_string(),
+            Generator::Enum(values) => values
+                .choose(rng)
+                .expect("enum values won't be empty")
+                .clone(),
+            Generator::Date(from, to, format) => format_date(rng.gen_range(*from..=*to), format),
+        }
+    }
+}
+
+fn pick<'a>(values: &[&'a str], rng: &mut impl Rng) -> &'a str {
+    values.choose(rng).expect("word lists won't be empty")
+}
+
+fn format_uuid(bytes: &[u8; 16]) -> String {
+    let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// 把 `YYYY-MM-DD` 解析为相对 1970-01-01 的天数
+fn parse_date(s: &str) -> Result<i64> {
+    let invalid = || anyhow!("Invalid date (expected YYYY-MM-DD): {}", s);
+    let mut parts = s.splitn(3, '-');
+    let mut next = || -> Result<i64> {
+        parts
+            .next()
+            .ok_or_else(invalid)?
+            .parse()
+            .map_err(|_| invalid())
+    };
+    let (y, m, d) = (next()?, next()?, next()?);
+    if !(1..=12).contains(&m) || !(1..=days_in_month(y, m)).contains(&d) {
+        return Err(invalid());
+    }
+    Ok(days_from_civil(y, m, d))
+}
+
+/// 支持 `%Y` `%m` `%d` `%b` 四种占位符
+fn format_date(days: i64, format: &str) -> String {
+    let (y, m, d) = civil_from_days(days);
+    format
+        .replace("%Y", &format!("{y:04}"))
+        .replace("%m", &format!("{m:02}"))
+        .replace("%d", &format!("{d:02}"))
+        .replace("%b", MONTHS[(m - 1) as usize])
+}
+
+fn days_in_month(y: i64, m: i64) -> i64 {
+    match m {
+        2 if (y % 4 == 0 && y % 100 != 0) || y % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (m + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400;
+    (if m <= 2 { y + 1 } else { y }, m, d)
+}
+
+fn default_max() -> i64 {
+    100
+}
+
+fn default_from() -> String {
+    "1970-01-01".to_string()
+}
+
+fn default_to() -> String {
+    "2000-12-31".to_string()
+}
+
+fn default_date_format() -> String {
+    "%Y-%m-%d".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = include_str!("../../fixtures/fake_schema.yaml");
+
+    #[test]
+    fn test_process_csv_fake_is_reproducible() -> Result<()> {
+        let mut a = Vec::new();
+        let mut b = Vec::new();
+        process_csv_fake(SCHEMA, 500, Some(42), &mut a)?;
+        process_csv_fake(SCHEMA, 500, Some(42), &mut b)?;
+        assert_eq!(a, b);
+
+        let mut reader = csv::Reader::from_reader(a.as_slice());
+        assert_eq!(
+            reader.headers()?,
+            vec![
+                "Name",
+                "Position",
+                "DOB",
+                "Nationality",
+                "Kit Number",
+                "Email",
+                "Id"
+            ]
+        );
+        let mut count = 0;
+        for record in reader.records() {
+            let record = record?;
+            let kit: i64 = record[4].parse()?;
+            assert!((1..=99).contains(&kit));
+            assert_eq!(record[6].len(), 36);
+            assert_eq!(&record[6][14..15], "4");
+            count += 1;
+        }
+        assert_eq!(count, 500);
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_csv_fake_known_answer() -> Result<()> {
+        // pins the seeded output, a change here breaks existing fixtures
+        let schema = "columns:\n  - name: N\n    kind: integer\n    min: 1\n    max: 1000\n";
+        let mut output = Vec::new();
+        process_csv_fake(schema, 3, Some(42), &mut output)?;
+        assert_eq!(String::from_utf8(output)?, "N\n515\n411\n98\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_date_round_trip() -> Result<()> {
+        assert_eq!(parse_date("1970-01-01")?, 0);
+        assert_eq!(
+            format_date(parse_date("2000-02-29")?, "%b %d, %Y"),
+            "Feb 29, 2000"
+        );
+        assert!(parse_date("2001-02-29").is_err());
+        assert!(parse_date("2001-13-01").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_csv_fake_rejects_bad_schema() {
+        let schema = "columns:\n  - name: N\n    kind: integer\n    min: 10\n    max: 1\n";
+        assert!(process_csv_fake(schema, 1, Some(1), &mut Vec::new()).is_err());
+        let schema = "columns:\n  - name: N\n    kind: enum\n    values: []\n";
+        assert!(process_csv_fake(schema, 1, Some(1), &mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+use super::csv_convert::{open_reader, record_to_json};
+use anyhow::{Result, bail};
+use minijinja::{Environment, Value, context};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 对每条记录渲染一次模板，结果依次写入 `writer`
+///
+/// 模板上下文就是 `process_csv` 构建的表头到值的映射，另外提供 `record`（整条记录，
+/// 用于访问 `record["Kit Number"]` 这类带空格的列名）和从 1 开始的 `index`。
+pub fn process_csv_render(
+    input: &str,
+    sheet: Option<&str>,
+    template: &str,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    let env = environment(template)?;
+    let tmpl = env.get_template("template")?;
+
+    for (index, record) in records(input, sheet)?.enumerate() {
+        let ctx = record_context(&record?, index);
+        writer.write_all(tmpl.render(&ctx)?.as_bytes())?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// 对每条记录渲染一次模板，每条记录写一个文件，文件名由 `pattern`（如 `out/{{Name}}.txt`）渲染得到
+///
+/// 只有文件名部分可以使用占位符，目录部分按原样使用。渲染出的文件名中的 `/`、`\`
+/// 和 NUL 会被替换为 `_`，因此数据无法把文件写到目录之外。两条记录渲染出相同文件名时
+/// 返回错误，避免相互覆盖。
+pub fn process_csv_render_files(
+    input: &str,
+    sheet: Option<&str>,
+    template: &str,
+    pattern: &str,
+) -> Result<Vec<PathBuf>> {
+    let pattern = Path::new(pattern);
+    let dir = pattern.parent().unwrap_or(Path::new(""));
+    let Some(file_pattern) = pattern.file_name().and_then(|name| name.to_str()) else {
+        bail!("Output pattern has no file name: {}", pattern.display());
+    };
+    let dir_str = dir.to_string_lossy();
+    if dir_str.contains("{{") || dir_str.contains("{%") {
+        bail!(
+            "Only the file name of the output pattern may use placeholders: {}",
+            pattern.display()
+        );
+    }
+
+    let mut env = environment(template)?;
+    env.add_template("pattern", file_pattern)?;
+    let tmpl = env.get_template("template")?;
+    let name = env.get_template("pattern")?;
+    if !dir.as_os_str().is_empty() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut seen = HashSet::new();
+    let mut ret = Vec::new();
+    for (index, record) in records(input, sheet)?.enumerate() {
+        let ctx = record_context(&record?, index);
+        let file_name = sanitize_file_name(&name.render(&ctx)?);
+        if matches!(file_name.as_str(), "" | "." | "..") {
+            bail!(
+                "Output pattern rendered an invalid file name for row {}: {:?}",
+                index + 1,
+                file_name
+            );
+        }
+        let path = dir.join(file_name);
+        if !seen.insert(path.clone()) {
+            bail!(
+                "Output pattern rendered a duplicate file name: {}",
+                path.display()
+            );
+        }
+
+        fs::write(&path, tmpl.render(&ctx)?)?;
+        ret.push(path);
+    }
+    Ok(ret)
+}
+
+/// 替换会改变路径含义的字符，文件名只能落在模式给定的目录中
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if matches!(c, '/' | '\\' | '\0') {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+fn environment(template: &str) -> Result<Environment<'_>> {
+    let mut env = Environment::new();
+    // 多条记录拼接输出时保留模板末尾的换行
+    env.set_keep_trailing_newline(true);
+    env.add_template("template", template)?;
+    Ok(env)
+}
+
+fn records(
+    input: &str,
+    sheet: Option<&str>,
+) -> Result<impl Iterator<Item = Result<serde_json::Value>>> {
+    let mut reader = open_reader(input, sheet)?;
+    let headers = reader.headers()?.clone();
+    Ok(reader
+        .into_records()
+        .map(move |record| Ok(record_to_json(&headers, &record?))))
+}
+
+fn record_context(record: &serde_json::Value, index: usize) -> Value {
+    let fields = Value::from_serialize(record);
+    context! {
+        record => fields.clone(),
+        index => index + 1,
+        ..fields
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = "Dear {{ Name }}, your kit number is {{ record[\"Kit Number\"] }}.\n";
+
+    #[test]
+    fn test_process_csv_render() -> Result<()> {
+        let mut output = Vec::new();
+        process_csv_render("assets/juventus.csv", None, TEMPLATE, &mut output)?;
+        let output = String::from_utf8(output)?;
+        let mut lines = output.lines();
+        assert_eq!(
+            lines.next(),
+            Some("Dear Wojciech Szczesny, your kit number is 1.")
+        );
+        assert_eq!(
+            lines.next(),
+            Some("Dear Mattia Perin, your kit number is 37.")
+        );
+        assert_eq!(output.lines().count(), 27);
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_csv_render_files_rejects_duplicates() -> Result<()> {
+        let dir = std::env::temp_dir().join("rstool_test_render_files");
+        let pattern = format!("{}/{{{{ Position }}}}.txt", dir.display());
+        let ret = process_csv_render_files("assets/juventus.csv", None, TEMPLATE, &pattern);
+        assert!(ret.is_err());
+
+        let pattern = format!("{}/{{{{ index }}}}-{{{{ Name }}}}.txt", dir.display());
+        let ret = process_csv_render_files("assets/juventus.csv", None, TEMPLATE, &pattern)?;
+        assert_eq!(ret.len(), 27);
+        assert_eq!(
+            fs::read_to_string(dir.join("2-Mattia Perin.txt"))?,
+            "Dear Mattia Perin, your kit number is 37.\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_csv_render_files_stays_in_directory() -> Result<()> {
+        let dir = std::env::temp_dir().join("rstool_test_render_traversal");
+        let input = dir.join("input.csv");
+        fs::create_dir_all(&dir)?;
+        fs::write(&input, "Name\n../../escape\n/etc/passwd\n")?;
+        let input = input.to_str().expect("temp dir is valid utf-8");
+
+        let out = dir.join("out");
+        let pattern = format!("{}/{{{{ Name }}}}.txt", out.display());
+        let ret = process_csv_render_files(input, None, "{{ Name }}", &pattern)?;
+        assert_eq!(
+            ret,
+            [out.join(".._.._escape.txt"), out.join("_etc_passwd.txt")]
+        );
+        assert!(!dir.join("escape.txt").exists());
+
+        for pattern in ["{{ Name }}/x.txt", "out/{{ Name }}/x.txt"] {
+            assert!(process_csv_render_files(input, None, "", pattern).is_err());
+        }
+        let dots = dir.join("dots.csv");
+        fs::write(&dots, "Name\n..\n")?;
+        let dots = dots.to_str().expect("temp dir is valid utf-8");
+        let pattern = format!("{}/{{{{ Name }}}}", out.display());
+        assert!(process_csv_render_files(dots, None, "", &pattern).is_err());
+        Ok(())
+    }
+}
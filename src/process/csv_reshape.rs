@@ -0,0 +1,182 @@
+use anyhow::{Result, anyhow, bail};
+use csv::{Reader, StringRecord, Writer};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+/// 长表转宽表：`columns` 列的每个取值成为新的一列，单元格内容取自 `values` 列
+///
+/// 行和列都按首次出现的顺序输出，缺失的单元格留空。
+/// 同一个索引和列组合出现多次时返回错误。
+pub fn process_csv_pivot(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    index: &[String],
+    columns: &str,
+    values: &str,
+) -> Result<()> {
+    let mut reader = Reader::from_reader(reader);
+    let headers = reader.headers()?.clone();
+    let index_pos = positions(&headers, index)?;
+    let columns_pos = position(&headers, columns)?;
+    let values_pos = position(&headers, values)?;
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut row_lookup: HashMap<Vec<String>, usize> = HashMap::new();
+    let mut cols: Vec<String> = Vec::new();
+    let mut col_lookup: HashMap<String, usize> = HashMap::new();
+    let mut cells: HashMap<(usize, usize), String> = HashMap::new();
+
+    for result in reader.records() {
+        let record = result?;
+        let key: Vec<String> = index_pos.iter().map(|&i| field(&record, i)).collect();
+        let col = field(&record, columns_pos);
+
+        let row = *row_lookup.entry(key.clone()).or_insert_with(|| {
+            rows.push(key.clone());
+            rows.len() - 1
+        });
+        let col_idx = *col_lookup.entry(col.clone()).or_insert_with(|| {
+            cols.push(col.clone());
+            cols.len() - 1
+        });
+
+        if cells
+            .insert((row, col_idx), field(&record, values_pos))
+            .is_some()
+        {
+            bail!("Duplicate entry for index {:?} and column {:?}", key, col);
+        }
+    }
+
+    let mut writer = Writer::from_writer(writer);
+    writer.write_record(index.iter().chain(cols.iter()))?;
+    for (row, key) in rows.iter().enumerate() {
+        let values = (0..cols.len()).map(|col| {
+            cells
+                .get(&(row, col))
+                .map(String::as_str)
+                .unwrap_or_default()
+        });
+        writer.write_record(key.iter().map(String::as_str).chain(values))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// 宽表转长表：除 `index` 外的列（或 `value_vars` 指定的列）展开为 `var_name`/`value_name` 两列
+///
+/// 空单元格会被跳过，因此 pivot 之后再 melt 可以还原原始数据。
+pub fn process_csv_melt(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    index: &[String],
+    value_vars: &[String],
+    var_name: &str,
+    value_name: &str,
+) -> Result<()> {
+    let mut reader = Reader::from_reader(reader);
+    let headers = reader.headers()?.clone();
+    let index_pos = positions(&headers, index)?;
+    let value_pos = if value_vars.is_empty() {
+        (0..headers.len())
+            .filter(|i| !index_pos.contains(i))
+            .collect()
+    } else {
+        positions(&headers, value_vars)?
+    };
+
+    let mut writer = Writer::from_writer(writer);
+    writer.write_record(
+        index
+            .iter()
+            .map(String::as_str)
+            .chain([var_name, value_name]),
+    )?;
+    for result in reader.records() {
+        let record = result?;
+        for &i in &value_pos {
+            let value = record.get(i).unwrap_or_default();
+            if value.is_empty() {
+                continue;
+            }
+            let ids = index_pos.iter().map(|&i| record.get(i).unwrap_or_default());
+            writer.write_record(ids.chain([&headers[i], value]))?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn position(headers: &StringRecord, name: &str) -> Result<usize> {
+    headers
+        .iter()
+        .position(|h| h == name)
+        .ok_or_else(|| anyhow!("Column not found: {}", name))
+}
+
+fn positions(headers: &StringRecord, names: &[String]) -> Result<Vec<usize>> {
+    names.iter().map(|name| position(headers, name)).collect()
+}
+
+fn field(record: &StringRecord, i: usize) -> String {
+    record.get(i).unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LONG: &str = "\
+Name,Position,Kit Number
+Szczesny,Goalkeeper,1
+Szczesny,Captain,no
+Perin,Goalkeeper,37
+Bonucci,Defender,19
+";
+
+    #[test]
+    fn test_process_csv_pivot_and_melt() -> Result<()> {
+        let mut wide = Vec::new();
+        process_csv_pivot(
+            &mut LONG.as_bytes(),
+            &mut wide,
+            &["Name".to_string()],
+            "Position",
+            "Kit Number",
+        )?;
+        assert_eq!(
+            String::from_utf8(wide.clone())?,
+            "\
+Name,Goalkeeper,Captain,Defender
+Szczesny,1,no,
+Perin,37,,
+Bonucci,,,19
+"
+        );
+
+        let mut long = Vec::new();
+        process_csv_melt(
+            &mut wide.as_slice(),
+            &mut long,
+            &["Name".to_string()],
+            &[],
+            "Position",
+            "Kit Number",
+        )?;
+        assert_eq!(String::from_utf8(long)?, LONG);
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_csv_pivot_rejects_duplicates() {
+        let input = "Name,Position,Kit Number\nPerin,Goalkeeper,37\nPerin,Goalkeeper,1\n";
+        let ret = process_csv_pivot(
+            &mut input.as_bytes(),
+            &mut Vec::new(),
+            &["Name".to_string()],
+            "Position",
+            "Kit Number",
+        );
+        assert!(ret.is_err());
+    }
+}
@@ -0,0 +1,128 @@
+use anyhow::{Result, anyhow};
+use calamine::{Data, Reader, open_workbook_auto};
+use rust_xlsxwriter::{Format, Workbook};
+use serde_json::Value;
+use std::path::Path;
+
+const EXCEL_EXTENSIONS: [&str; 4] = ["xlsx", "xlsm", "xlsb", "xls"];
+
+/// 根据扩展名判断输入是否为 Excel 工作簿
+pub(crate) fn is_excel(input: &str) -> bool {
+    Path::new(input)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| EXCEL_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// 读取工作簿中的一个工作表（默认第一个）并转换为 CSV 字节，第一行作为表头
+pub(crate) fn read_excel(input: &str, sheet: Option<&str>) -> Result<Vec<u8>> {
+    let mut workbook = open_workbook_auto(input)?;
+    let range = match sheet {
+        Some(name) => workbook.worksheet_range(name)?,
+        None => workbook
+            .worksheet_range_at(0)
+            .ok_or_else(|| anyhow!("Workbook has no sheets: {}", input))??,
+    };
+
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for row in range.rows() {
+        writer.write_record(row.iter().map(cell_to_string))?;
+    }
+    Ok(writer.into_inner()?)
+}
+
+/// 将记录写成 xlsx：表头加粗，数字和布尔值按类型写入单元格
+pub(crate) fn to_xlsx(ret: &[Value]) -> Result<Vec<u8>> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    let bold = Format::new().set_bold();
+
+    let headers: Vec<&String> = match ret.first() {
+        Some(Value::Object(map)) => map.keys().collect(),
+        _ => Vec::new(),
+    };
+    for (col, header) in headers.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, *header, &bold)?;
+    }
+
+    for (row, record) in ret.iter().enumerate() {
+        let row = row as u32 + 1;
+        for (col, header) in headers.iter().enumerate() {
+            let col = col as u16;
+            match record.get(header.as_str()) {
+                Some(Value::String(s)) => {
+                    if let Some(n) = as_number(s) {
+                        worksheet.write_number(row, col, n)?;
+                    } else if let Ok(b) = s.parse::<bool>() {
+                        worksheet.write_boolean(row, col, b)?;
+                    } else {
+                        worksheet.write_string(row, col, s)?;
+                    }
+                }
+                Some(Value::Number(n)) => {
+                    worksheet.write_number(row, col, n.as_f64().unwrap_or_default())?;
+                }
+                Some(Value::Bool(b)) => {
+                    worksheet.write_boolean(row, col, *b)?;
+                }
+                Some(Value::Null) | None => {}
+                Some(v) => {
+                    worksheet.write_string(row, col, v.to_string())?;
+                }
+            }
+        }
+    }
+
+    Ok(workbook.save_to_buffer()?)
+}
+
+/// 只有有限且能原样写回的文本才当作数字，保留 `00123`、`1e5`、`nan` 等原文
+fn as_number(s: &str) -> Option<f64> {
+    s.parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite() && n.to_string() == s)
+}
+
+fn cell_to_string(cell: &Data) -> String {
+    match cell {
+        Data::DateTime(dt) if dt.is_datetime() => {
+            let (y, m, d, hh, mm, ss, _) = dt.to_ymd_hms_milli();
+            if (hh, mm, ss) == (0, 0, 0) {
+                format!("{y:04}-{m:02}-{d:02}")
+            } else {
+                format!("{y:04}-{m:02}-{d:02} {hh:02}:{mm:02}:{ss:02}")
+            }
+        }
+        _ => cell.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_xlsx_round_trip() -> Result<()> {
+        let ret = vec![
+            json!({"Name": "Mattia Perin", "Kit Number": "37", "Active": "true"}),
+            json!({"Name": "Wojciech Szczesny", "Kit Number": "1", "Active": "false"}),
+            json!({"Name": "nan", "Kit Number": "NaN", "Active": "inf"}),
+            json!({"Name": "inf", "Kit Number": "00123", "Active": "1e5"}),
+            json!({"Name": "-inf", "Kit Number": "1.5", "Active": "+7"}),
+        ];
+        let path = std::env::temp_dir().join("rstool_test_xlsx_round_trip.xlsx");
+        std::fs::write(&path, to_xlsx(&ret)?)?;
+
+        let path = path.to_str().expect("temp dir is valid utf-8");
+        assert!(is_excel(path));
+        let content = read_excel(path, None)?;
+        assert_eq!(
+            String::from_utf8(content)?,
+            "Name,Kit Number,Active\nMattia Perin,37,true\nWojciech Szczesny,1,false\n\
+             nan,NaN,inf\ninf,00123,1e5\n-inf,1.5,+7\n"
+        );
+        assert!(read_excel(path, Some("missing")).is_err());
+        Ok(())
+    }
+}
@@ -1,46 +1,466 @@
-use rand::seq::SliceRandom;
+use crate::cli::GenPassFormat;
+use anyhow::{Result, anyhow, bail};
+use rand::{CryptoRng, RngCore, seq::SliceRandom};
+use serde::Serialize;
+use std::io::Write;
 
 const UPPER: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
 const LOWER: &[u8] = b"abcdefghijkmnopqrstuvwxyz";
-const NUMBER: &[u8] = b"123456789";
-const SYMBOL: &[u8] = b"!@#$%^&*_";
+pub(crate) const NUMBER: &[u8] = b"123456789";
+pub(crate) const SYMBOL: &[u8] = b"!@#$%^&*_";
+
+/// Upper bound for generated passwords, large enough for long service tokens.
+pub const MAX_PASSWORD_LENGTH: usize = 8192;
+
+/// Options for `process_genpass_config`.
+///
+/// With the built-in classes every enabled class contributes at least one
+/// character (or its `min_*`). When `charset` is set it replaces the built-in
+/// classes and the class flags; the `min_*` values then count the characters of
+/// the charset that are uppercase, lowercase, digits or anything else.
+#[derive(Debug, Clone)]
+pub struct GenPassConfig {
+    pub length: usize,
+    pub upper: bool,
+    pub lower: bool,
+    pub number: bool,
+    pub symbol: bool,
+    pub charset: Option<String>,
+    /// Replaces the built-in symbol set
+    pub symbols_set: Option<String>,
+    /// Characters that never appear in the password
+    pub exclude: String,
+    pub min_upper: usize,
+    pub min_lower: usize,
+    pub min_number: usize,
+    pub min_symbol: usize,
+}
+
+/// A generated password with its entropy in bits and zxcvbn score.
+#[derive(Debug, Serialize)]
+pub struct GeneratedPassword {
+    pub password: String,
+    pub entropy: f64,
+    pub score: u8,
+}
+
+pub(crate) type ClassPredicate = fn(&char) -> bool;
+
+pub(crate) struct CharClass {
+    pub(crate) chars: Vec<char>,
+    pub(crate) min: usize,
+}
+
+impl Default for GenPassConfig {
+    fn default() -> Self {
+        Self {
+            length: 16,
+            upper: true,
+            lower: true,
+            number: true,
+            symbol: true,
+            charset: None,
+            symbols_set: None,
+            exclude: String::new(),
+            min_upper: 0,
+            min_lower: 0,
+            min_number: 0,
+            min_symbol: 0,
+        }
+    }
+}
+
+impl GenPassConfig {
+    /// Entropy in bits of a password drawn uniformly from the pool. The
+    /// per-class minimums make the real value slightly lower.
+    pub fn entropy(&self) -> Result<f64> {
+        let (pool, _) = self.classes()?;
+        Ok(self.length as f64 * (pool.len() as f64).log2())
+    }
+
+    /// Like `classes`, but also check the length against the class minimums.
+    pub(crate) fn checked_classes(&self) -> Result<(Vec<char>, Vec<CharClass>)> {
+        let (pool, classes) = self.classes()?;
+        let length = self.length;
+        let required = classes
+            .iter()
+            .try_fold(0usize, |acc, class| acc.checked_add(class.min))
+            .ok_or_else(|| anyhow!("The --min-* counts add up to more than any password length"))?;
+        if length == 0 || length < required {
+            bail!(
+                "Password length {} is too short for the {} characters required by the enabled classes",
+                length,
+                required
+            );
+        }
+        if length > MAX_PASSWORD_LENGTH {
+            bail!(
+                "Password length {} exceeds the maximum of {}",
+                length,
+                MAX_PASSWORD_LENGTH
+            );
+        }
+        Ok((pool, classes))
+    }
+
+    /// Resolve the character pool and the classes with their minimum counts.
+    fn classes(&self) -> Result<(Vec<char>, Vec<CharClass>)> {
+        let mut pool = Vec::new();
+        let mut classes = Vec::new();
+
+        if let Some(charset) = &self.charset {
+            pool = self.filter(charset.chars());
+            if pool.is_empty() {
+                bail!("Charset is empty after exclusions");
+            }
+            let predicates: [(&str, ClassPredicate, usize); 4] = [
+                ("upper", char::is_ascii_uppercase, self.min_upper),
+                ("lower", char::is_ascii_lowercase, self.min_lower),
+                ("digits", char::is_ascii_digit, self.min_number),
+                ("symbols", |c| !c.is_alphanumeric(), self.min_symbol),
+            ];
+            for (name, predicate, min) in predicates {
+                if min == 0 {
+                    continue;
+                }
+                let chars: Vec<char> = pool.iter().copied().filter(predicate).collect();
+                if chars.is_empty() {
+                    bail!("Charset has no characters for --min-{}", name);
+                }
+                classes.push(CharClass { chars, min });
+            }
+        } else {
+            let symbols = self
+                .symbols_set
+                .as_deref()
+                .map(str::as_bytes)
+                .unwrap_or(SYMBOL);
+            let builtin = [
+                ("upper", self.upper, UPPER, self.min_upper),
+                ("lower", self.lower, LOWER, self.min_lower),
+                ("digits", self.number, NUMBER, self.min_number),
+                ("symbols", self.symbol, symbols, self.min_symbol),
+            ];
+            for (name, enabled, set, min) in builtin {
+                if !enabled {
+                    if min > 0 {
+                        bail!("--min-{} requires that class to be enabled", name);
+                    }
+                    continue;
+                }
+                let chars = self.filter(String::from_utf8_lossy(set).chars());
+                if chars.is_empty() {
+                    bail!("No {} characters left after exclusions", name);
+                }
+                pool.extend_from_slice(&chars);
+                classes.push(CharClass {
+                    chars,
+                    min: min.max(1),
+                });
+            }
+            if pool.is_empty() {
+                bail!("At least one character class must be enabled");
+            }
+        }
+
+        Ok((pool, classes))
+    }
+
+    fn filter(&self, chars: impl Iterator<Item = char>) -> Vec<char> {
+        let mut ret: Vec<char> = Vec::new();
+        for c in chars {
+            if !self.exclude.contains(c) && !ret.contains(&c) {
+                ret.push(c);
+            }
+        }
+        ret
+    }
+}
 
 pub fn process_genpass(
-    length: u8,
+    length: usize,
     upper: bool,
     lower: bool,
     number: bool,
     symbol: bool,
-) -> anyhow::Result<String> {
-    let mut rng = rand::thread_rng();
-    let mut password = Vec::new();
-    let mut chars = Vec::new();
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<String> {
+    process_genpass_config(
+        &GenPassConfig {
+            length,
+            upper,
+            lower,
+            number,
+            symbol,
+            ..Default::default()
+        },
+        rng,
+    )
+}
+
+pub fn process_genpass_config(
+    config: &GenPassConfig,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<String> {
+    let (pool, classes) = config.checked_classes()?;
+    let length = config.length;
+    let mut password = Vec::with_capacity(length);
 
-    if upper {
-        chars.extend_from_slice(UPPER);
-        password.push(*UPPER.choose(&mut rng).expect("UPPER won't be empty"));
+    for class in &classes {
+        for _ in 0..class.min {
+            password.push(*class.chars.choose(rng).expect("class won't be empty"));
+        }
     }
-    if lower {
-        chars.extend_from_slice(LOWER);
-        password.push(*LOWER.choose(&mut rng).expect("LOWER won't be empty"));
+
+    for _ in 0..(length - password.len()) {
+        let c = pool
+            .choose(rng)
+            .expect("chars won't be empty in this context");
+        password.push(*c);
     }
-    if number {
-        chars.extend_from_slice(NUMBER);
-        password.push(*NUMBER.choose(&mut rng).expect("NUMBER won't be empty"));
+
+    password.shuffle(rng);
+
+    Ok(password.into_iter().collect())
+}
+
+/// Write generated passwords as plain lines, a json array or csv rows.
+pub fn process_genpass_output(
+    passwords: &[GeneratedPassword],
+    format: GenPassFormat,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    match format {
+        GenPassFormat::Text => {
+            for p in passwords {
+                writeln!(writer, "{}", p.password)?;
+            }
+        }
+        GenPassFormat::Json => {
+            serde_json::to_writer_pretty(&mut *writer, passwords)?;
+            writeln!(writer)?;
+        }
+        GenPassFormat::Csv => {
+            let mut wtr = csv::Writer::from_writer(writer);
+            for p in passwords {
+                wtr.serialize(p)?;
+            }
+            wtr.flush()?;
+        }
     }
-    if symbol {
-        chars.extend_from_slice(SYMBOL);
-        password.push(*SYMBOL.choose(&mut rng).expect("SYMBOL won't be empty"));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_process_genpass_lengths() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        for length in [4, 16, 255, 256, 4096, MAX_PASSWORD_LENGTH] {
+            let ret = process_genpass(length, true, true, true, true, &mut rng)?;
+            assert_eq!(ret.len(), length);
+            assert!(ret.bytes().any(|c| UPPER.contains(&c)));
+            assert!(ret.bytes().any(|c| LOWER.contains(&c)));
+            assert!(ret.bytes().any(|c| NUMBER.contains(&c)));
+            assert!(ret.bytes().any(|c| SYMBOL.contains(&c)));
+        }
+
+        let ret = process_genpass(1, false, false, true, false, &mut rng)?;
+        assert_eq!(ret.len(), 1);
+        assert!(NUMBER.contains(&ret.as_bytes()[0]));
+        Ok(())
     }
 
-    for _ in 0..(length - password.len() as u8) {
-        let c = chars
-            .choose(&mut rng)
-            .expect("chars won't be empty in this context");
-        password.push(*c);
+    #[test]
+    fn test_process_genpass_rejects_impossible_configs() {
+        let mut rng = rand::thread_rng();
+        assert!(process_genpass(16, false, false, false, false, &mut rng).is_err());
+        assert!(process_genpass(0, true, false, false, false, &mut rng).is_err());
+        assert!(process_genpass(3, true, true, true, true, &mut rng).is_err());
+        assert!(
+            process_genpass(MAX_PASSWORD_LENGTH + 1, true, true, true, true, &mut rng).is_err()
+        );
+    }
+
+    #[test]
+    fn test_process_genpass_config_sets_and_minimums() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let config = GenPassConfig {
+            length: 24,
+            symbols_set: Some("-_.".to_string()),
+            exclude: "ABCDEFGHJKLMNPQRSTUVWXY".to_string(),
+            min_upper: 2,
+            min_number: 3,
+            ..Default::default()
+        };
+        for _ in 0..20 {
+            let ret = process_genpass_config(&config, &mut rng)?;
+            assert_eq!(ret.chars().count(), 24);
+            assert!(ret.chars().filter(|&c| c == 'Z').count() >= 2);
+            assert!(ret.chars().filter(char::is_ascii_digit).count() >= 3);
+            assert!(
+                ret.chars()
+                    .filter(|c| !c.is_alphanumeric())
+                    .all(|c| "-_.".contains(c))
+            );
+        }
+
+        let config = GenPassConfig {
+            length: 12,
+            charset: Some("abc012".to_string()),
+            exclude: "0".to_string(),
+            min_number: 4,
+            ..Default::default()
+        };
+        let ret = process_genpass_config(&config, &mut rng)?;
+        assert!(ret.chars().all(|c| "abc12".contains(c)));
+        assert!(ret.chars().filter(char::is_ascii_digit).count() >= 4);
+        Ok(())
     }
 
-    password.shuffle(&mut rng);
+    #[test]
+    fn test_process_genpass_config_rejects_impossible_configs() {
+        let mut rng = rand::thread_rng();
+        let configs = [
+            GenPassConfig {
+                symbol: false,
+                min_symbol: 1,
+                ..Default::default()
+            },
+            GenPassConfig {
+                exclude: "123456789".to_string(),
+                ..Default::default()
+            },
+            GenPassConfig {
+                length: 4,
+                min_upper: 2,
+                ..Default::default()
+            },
+            GenPassConfig {
+                charset: Some("abc".to_string()),
+                min_number: 1,
+                ..Default::default()
+            },
+            GenPassConfig {
+                charset: Some("abc".to_string()),
+                exclude: "abc".to_string(),
+                ..Default::default()
+            },
+            GenPassConfig {
+                min_upper: usize::MAX,
+                min_lower: 2,
+                ..Default::default()
+            },
+        ];
+        for config in configs {
+            assert!(
+                process_genpass_config(&config, &mut rng).is_err(),
+                "{config:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_process_genpass_output() -> Result<()> {
+        let passwords = vec![
+            GeneratedPassword {
+                password: "a,b".to_string(),
+                entropy: 12.5,
+                score: 0,
+            },
+            GeneratedPassword {
+                password: "U&o2JNaNyaqF".to_string(),
+                entropy: 72.0,
+                score: 4,
+            },
+        ];
+
+        let mut output = Vec::new();
+        process_genpass_output(&passwords, GenPassFormat::Csv, &mut output)?;
+        assert_eq!(
+            String::from_utf8(output)?,
+            "password,entropy,score\n\"a,b\",12.5,0\nU&o2JNaNyaqF,72.0,4\n"
+        );
+
+        let mut output = Vec::new();
+        process_genpass_output(&passwords, GenPassFormat::Json, &mut output)?;
+        let json: serde_json::Value = serde_json::from_slice(&output)?;
+        assert_eq!(json[1]["password"], "U&o2JNaNyaqF");
+        assert_eq!(json[1]["score"], 4);
+
+        let mut output = Vec::new();
+        process_genpass_output(&passwords, GenPassFormat::Text, &mut output)?;
+        assert_eq!(String::from_utf8(output)?, "a,b\nU&o2JNaNyaqF\n");
+
+        let config = GenPassConfig {
+            length: 10,
+            charset: Some("0123456789abcdef".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(config.entropy()?, 40.0);
+        Ok(())
+    }
 
-    Ok(String::from_utf8(password)?)
+    #[test]
+    fn test_process_genpass_known_answer() -> Result<()> {
+        let mut rng = ChaCha20Rng::seed_from_u64(42);
+        let ret = process_genpass(16, true, true, true, true, &mut rng)?;
+        assert_eq!(ret, "EZz3*3M1WbgH7*kv");
+        let ret = process_genpass(16, true, true, true, true, &mut rng)?;
+        assert_eq!(ret, "&mrdF%Fpi*So8EUN");
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn prop_genpass_keeps_class_guarantees(
+            seed: u64,
+            length in 4usize..256,
+            upper: bool,
+            lower: bool,
+            number: bool,
+            symbol: bool,
+        ) {
+            prop_assume!(upper || lower || number || symbol);
+            let mut rng = ChaCha20Rng::seed_from_u64(seed);
+            let ret = process_genpass(length, upper, lower, number, symbol, &mut rng).unwrap();
+            prop_assert_eq!(ret.len(), length);
+            for (enabled, set) in [(upper, UPPER), (lower, LOWER), (number, NUMBER), (symbol, SYMBOL)] {
+                prop_assert_eq!(ret.bytes().any(|c| set.contains(&c)), enabled);
+            }
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(8))]
+
+        #[test]
+        fn prop_genpass_is_uniform_over_charset(seed: u64) {
+            const CHARSET: &str = "0123456789abcdef";
+            let config = GenPassConfig {
+                length: MAX_PASSWORD_LENGTH,
+                charset: Some(CHARSET.to_string()),
+                ..Default::default()
+            };
+            let mut rng = ChaCha20Rng::seed_from_u64(seed);
+            let mut counts = [0usize; 16];
+            for _ in 0..8 {
+                for c in process_genpass_config(&config, &mut rng).unwrap().chars() {
+                    counts[CHARSET.find(c).unwrap()] += 1;
+                }
+            }
+            // chi-square with 15 degrees of freedom, 53 is p < 1e-6
+            let expected = (8 * MAX_PASSWORD_LENGTH / 16) as f64;
+            let chi2: f64 = counts
+                .iter()
+                .map(|&n| (n as f64 - expected).powi(2) / expected)
+                .sum();
+            prop_assert!(chi2 < 53.0, "chi2 = {chi2}, counts = {counts:?}");
+        }
+    }
 }
@@ -0,0 +1,212 @@
+use crate::cli::HashAlgorithm;
+use anyhow::{Result, anyhow, bail};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Version};
+use pbkdf2::Pbkdf2;
+use rand::{CryptoRng, RngCore};
+use scrypt::Scrypt;
+
+/// Hash parameters, `None` picks the OWASP recommended value.
+#[derive(Debug, Clone, Copy)]
+pub struct HashPwOptions {
+    pub algo: HashAlgorithm,
+    /// argon2id memory cost in KiB
+    pub m_cost: Option<u32>,
+    /// argon2id iterations
+    pub t_cost: Option<u32>,
+    /// argon2id or scrypt parallelism
+    pub p_cost: Option<u32>,
+    /// bcrypt cost
+    pub cost: Option<u32>,
+    /// scrypt log2 of N
+    pub log_n: Option<u8>,
+    /// scrypt block size
+    pub r: Option<u32>,
+    /// pbkdf2-sha256 iterations
+    pub rounds: Option<u32>,
+}
+
+impl Default for HashPwOptions {
+    fn default() -> Self {
+        Self {
+            algo: HashAlgorithm::Argon2id,
+            m_cost: None,
+            t_cost: None,
+            p_cost: None,
+            cost: None,
+            log_n: None,
+            r: None,
+            rounds: None,
+        }
+    }
+}
+
+/// Hash `password` with a random salt. argon2id, scrypt and pbkdf2 produce
+/// PHC strings, bcrypt its usual `$2b$` form.
+pub fn process_hashpw(
+    password: &[u8],
+    opts: &HashPwOptions,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<String> {
+    let salt = SaltString::generate(&mut *rng);
+    let hash = match opts.algo {
+        HashAlgorithm::Argon2id => {
+            let params = argon2::Params::new(
+                opts.m_cost.unwrap_or(argon2::Params::DEFAULT_M_COST),
+                opts.t_cost.unwrap_or(argon2::Params::DEFAULT_T_COST),
+                opts.p_cost.unwrap_or(argon2::Params::DEFAULT_P_COST),
+                None,
+            )
+            .map_err(|e| anyhow!("Invalid argon2id parameters: {e}"))?;
+            Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                .hash_password(password, &salt)
+                .map_err(|e| anyhow!("{e}"))?
+                .to_string()
+        }
+        HashAlgorithm::Bcrypt => {
+            let mut salt = [0u8; 16];
+            rng.fill_bytes(&mut salt);
+            // plain hash_with_salt would silently ignore everything past 72 bytes
+            bcrypt::non_truncating_hash_with_salt(
+                password,
+                opts.cost.unwrap_or(bcrypt::DEFAULT_COST),
+                salt,
+            )
+            .map_err(bcrypt_error)?
+            .format_for_version(bcrypt::Version::TwoB)
+        }
+        HashAlgorithm::Scrypt => {
+            let params = scrypt::Params::new(
+                opts.log_n.unwrap_or(scrypt::Params::RECOMMENDED_LOG_N),
+                opts.r.unwrap_or(scrypt::Params::RECOMMENDED_R),
+                opts.p_cost.unwrap_or(scrypt::Params::RECOMMENDED_P),
+                scrypt::Params::RECOMMENDED_LEN,
+            )
+            .map_err(|e| anyhow!("Invalid scrypt parameters: {e}"))?;
+            Scrypt
+                .hash_password_customized(password, None, None, params, &salt)
+                .map_err(|e| anyhow!("{e}"))?
+                .to_string()
+        }
+        HashAlgorithm::Pbkdf2 => {
+            let params = pbkdf2::Params {
+                rounds: opts
+                    .rounds
+                    .unwrap_or(pbkdf2::Params::RECOMMENDED_ROUNDS as u32),
+                output_length: 32,
+            };
+            Pbkdf2
+                .hash_password_customized(
+                    password,
+                    Some(pbkdf2::Algorithm::Pbkdf2Sha256.ident()),
+                    None,
+                    params,
+                    &salt,
+                )
+                .map_err(|e| anyhow!("{e}"))?
+                .to_string()
+        }
+    };
+    Ok(hash)
+}
+
+/// Check `password` against a hash made by `process_hashpw` or another tool
+/// using the same formats. The algorithm is taken from the hash.
+pub fn process_hashpw_verify(password: &[u8], hash: &str) -> Result<bool> {
+    if hash.starts_with("$2a$") || hash.starts_with("$2b$") || hash.starts_with("$2y$") {
+        return bcrypt::non_truncating_verify(password, hash).map_err(bcrypt_error);
+    }
+
+    let parsed = PasswordHash::new(hash).map_err(|e| anyhow!("Invalid hash: {e}"))?;
+    let result = match parsed.algorithm.as_str() {
+        "argon2id" | "argon2i" | "argon2d" => Argon2::default().verify_password(password, &parsed),
+        "scrypt" => Scrypt.verify_password(password, &parsed),
+        "pbkdf2" | "pbkdf2-sha256" | "pbkdf2-sha512" => Pbkdf2.verify_password(password, &parsed),
+        algo => bail!("Unsupported hash algorithm: {}", algo),
+    };
+    match result {
+        Ok(()) => Ok(true),
+        Err(argon2::password_hash::Error::Password) => Ok(false),
+        Err(e) => Err(anyhow!("{e}")),
+    }
+}
+
+fn bcrypt_error(e: bcrypt::BcryptError) -> anyhow::Error {
+    match e {
+        // the limit counts the trailing NUL bcrypt appends
+        bcrypt::BcryptError::Truncation(len) => anyhow!(
+            "bcrypt takes at most 71 bytes of password, this one is {} bytes; use --algo argon2id for longer passwords",
+            len - 1
+        ),
+        e => e.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_rng;
+
+    // cheap parameters, the defaults are deliberately slow
+    fn fast(algo: HashAlgorithm) -> HashPwOptions {
+        HashPwOptions {
+            algo,
+            m_cost: Some(1024),
+            t_cost: Some(1),
+            cost: Some(4),
+            log_n: Some(8),
+            rounds: Some(1000),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_process_hashpw_roundtrip() -> Result<()> {
+        let mut rng = get_rng(Some(3));
+        let algos = [
+            (HashAlgorithm::Argon2id, "$argon2id$v=19$m=1024,t=1,p=1$"),
+            (HashAlgorithm::Bcrypt, "$2b$04$"),
+            (HashAlgorithm::Scrypt, "$scrypt$ln=8,r=8,p=1$"),
+            (HashAlgorithm::Pbkdf2, "$pbkdf2-sha256$i=1000,l=32$"),
+        ];
+        for (algo, prefix) in algos {
+            let hash = process_hashpw(b"hunter2", &fast(algo), &mut rng)?;
+            assert!(hash.starts_with(prefix), "{hash}");
+            assert!(process_hashpw_verify(b"hunter2", &hash)?, "{hash}");
+            assert!(!process_hashpw_verify(b"hunter3", &hash)?, "{hash}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_hashpw_verify_known_hashes() -> Result<()> {
+        // made independently with Python's hashlib and libxcrypt
+        let hashes = [
+            "$pbkdf2-sha256$i=1000,l=32$c2FsdHNhbHRzYWx0c2FsdA$8nX7hwFEzIB8aPajJTYK8weHQc5Ngz0pFVAKvSu4jQA",
+            "$2b$04$abcdefghijklmnopqrstuughE8Ev8uGFaUgY2cNEySvxngrb/Jzdm",
+        ];
+        for hash in hashes {
+            assert!(process_hashpw_verify(b"password", hash)?, "{hash}");
+            assert!(!process_hashpw_verify(b"Password", hash)?, "{hash}");
+        }
+        assert!(process_hashpw_verify(b"password", "$md5$abc").is_err());
+        assert!(process_hashpw_verify(&[b'a'; 100], hashes[1]).is_err());
+        assert!(process_hashpw_verify(b"password", "not a hash").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_hashpw_bcrypt_rejects_long_passwords() -> Result<()> {
+        let mut rng = get_rng(Some(4));
+        let opts = fast(HashAlgorithm::Bcrypt);
+        let hash = process_hashpw(&[b'a'; 71], &opts, &mut rng)?;
+        assert!(process_hashpw_verify(&[b'a'; 71], &hash)?);
+
+        // with truncation these would all share one hash
+        let err = process_hashpw(&[b'a'; 72], &opts, &mut rng).unwrap_err();
+        assert!(err.to_string().contains("71 bytes"), "{err}");
+        assert!(process_hashpw(&[b'a'; 200], &opts, &mut rng).is_err());
+        assert!(process_hashpw_verify(&[b'a'; 72], &hash).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::{Result, bail};
+use sha1::{Digest, Sha1};
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+
+/// Give up on rejecting breached passwords after this many candidates.
+const MAX_ATTEMPTS: usize = 100;
+
+/// A downloaded "Pwned Passwords" SHA-1 list ordered by hash.
+///
+/// Each line is `HASH:COUNT`. Lookups binary-search the file with seeks, so
+/// the multi-gigabyte list is never loaded into memory.
+pub struct HibpList {
+    reader: BufReader<File>,
+    len: u64,
+}
+
+impl HibpList {
+    pub fn open(path: &str) -> Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            reader: BufReader::new(file),
+            len,
+        })
+    }
+
+    /// Number of times `password` appears in breaches, 0 if it is not listed.
+    pub fn lookup(&mut self, password: &str) -> Result<u64> {
+        let hash = format!("{:X}", Sha1::digest(password.as_bytes()));
+
+        let (mut lo, mut hi) = (0, self.len);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let Some((start, next, line)) = self.line_from(mid)? else {
+                hi = mid;
+                continue;
+            };
+            if start >= hi {
+                hi = mid;
+                continue;
+            }
+            let Some((line_hash, count)) = line.split_once(':') else {
+                bail!("Invalid line in breach list at byte {}: {:?}", start, line);
+            };
+            match hash.as_str().cmp(&line_hash.to_ascii_uppercase()) {
+                Ordering::Equal => return Ok(count.trim().parse()?),
+                Ordering::Less => hi = mid,
+                Ordering::Greater => lo = next,
+            }
+        }
+        Ok(0)
+    }
+
+    /// Read the first line starting at or after `pos`, returning its start
+    /// offset, the offset of the line after it and its trimmed content.
+    fn line_from(&mut self, pos: u64) -> Result<Option<(u64, u64, String)>> {
+        let mut start = pos;
+        if pos > 0 {
+            self.reader.seek(SeekFrom::Start(pos - 1))?;
+            let mut skipped = Vec::new();
+            start = pos - 1 + self.reader.read_until(b'\n', &mut skipped)? as u64;
+        } else {
+            self.reader.seek(SeekFrom::Start(0))?;
+        }
+
+        let mut line = String::new();
+        let read = self.reader.read_line(&mut line)? as u64;
+        if read == 0 {
+            return Ok(None);
+        }
+        Ok(Some((start, start + read, line.trim_end().to_string())))
+    }
+}
+
+/// Call `generate` until its password is not in the breach list.
+pub fn process_hibp_reject(
+    mut generate: impl FnMut() -> Result<String>,
+    list: &mut HibpList,
+) -> Result<String> {
+    for _ in 0..MAX_ATTEMPTS {
+        let password = generate()?;
+        if list.lookup(&password)? == 0 {
+            return Ok(password);
+        }
+    }
+    bail!(
+        "Every candidate in {} attempts was found in the breach list",
+        MAX_ATTEMPTS
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hibp_lookup() -> Result<()> {
+        let mut list = HibpList::open("fixtures/hibp_sample.txt")?;
+        assert_eq!(list.lookup("password")?, 3824);
+        assert_eq!(list.lookup("123456")?, 12466);
+        assert_eq!(list.lookup("letmein")?, 10004);
+        assert_eq!(list.lookup("qwerty")?, 32095);
+        assert_eq!(list.lookup("hunter2")?, 1118);
+        assert_eq!(list.lookup("correct horse battery staple")?, 0);
+        assert_eq!(list.lookup("")?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_hibp_reject() -> Result<()> {
+        let mut list = HibpList::open("fixtures/hibp_sample.txt")?;
+        let mut candidates = ["password", "qwerty", "U&o2JNaNyaqF"].into_iter();
+        let ret = process_hibp_reject(|| Ok(candidates.next().unwrap().to_string()), &mut list)?;
+        assert_eq!(ret, "U&o2JNaNyaqF");
+
+        assert!(process_hibp_reject(|| Ok("123456".to_string()), &mut list).is_err());
+        Ok(())
+    }
+}
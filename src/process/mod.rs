@@ -1,12 +1,64 @@
 mod b64;
+mod check;
 mod csv_convert;
+mod csv_fake;
+mod csv_render;
+mod csv_reshape;
+mod excel;
 mod gen_pass;
+mod hashpw;
+mod hibp;
+mod otp;
+mod passphrase;
+mod pattern;
+mod policy;
+mod pronounceable;
+mod site;
+mod strength;
 mod text;
+mod token;
+mod xml;
 
 pub use b64::process_decode;
 pub use b64::process_encode;
+pub use check::process_genpass_check;
+pub use csv_convert::csv_to_json;
+pub use csv_convert::csv_to_json_parallel;
 pub use csv_convert::process_csv;
+pub use csv_convert::process_csv_decode;
+pub use csv_convert::process_csv_parallel;
+pub use csv_fake::process_csv_fake;
+pub use csv_render::process_csv_render;
+pub use csv_render::process_csv_render_files;
+pub use csv_reshape::process_csv_melt;
+pub use csv_reshape::process_csv_pivot;
+pub use gen_pass::GenPassConfig;
+pub use gen_pass::GeneratedPassword;
 pub use gen_pass::process_genpass;
+pub use gen_pass::process_genpass_config;
+pub use gen_pass::process_genpass_output;
+pub use hashpw::HashPwOptions;
+pub use hashpw::process_hashpw;
+pub use hashpw::process_hashpw_verify;
+pub use hibp::HibpList;
+pub use hibp::process_hibp_reject;
+pub use otp::OtpOptions;
+pub use otp::process_otp_generate;
+pub use otp::process_otp_new;
+pub use otp::process_otp_verify;
+pub use passphrase::process_passphrase;
+pub use pattern::process_genpass_pattern;
+pub use policy::PasswordPolicy;
+pub use policy::load_policy;
+pub use policy::process_policy_enforce;
+pub use pronounceable::process_genpass_pronounceable;
+pub use site::process_site_password;
+pub use strength::StrengthReport;
+pub use strength::process_min_score;
+pub use strength::process_strength;
 pub use text::process_text_generate;
 pub use text::process_text_sign;
 pub use text::process_text_verify;
+pub use token::TokenClock;
+pub use token::process_token;
+pub use xml::XmlOptions;
@@ -0,0 +1,118 @@
+use anyhow::{Result, bail};
+use rand::seq::SliceRandom;
+use rand::{CryptoRng, Rng, RngCore};
+use std::collections::HashSet;
+
+/// EFF long wordlist, 7776 words (about 12.9 bits per word).
+const DEFAULT_WORDLIST: &str = include_str!("../../assets/eff_large_wordlist.txt");
+
+pub const MAX_PASSPHRASE_WORDS: usize = 64;
+
+/// Generate a diceware-style passphrase and return it with its entropy in bits.
+///
+/// `wordlist` is the content of a wordlist file, either one word per line or the
+/// EFF format (`11111<TAB>abacus`). The embedded EFF long list is used when it's `None`.
+pub fn process_passphrase(
+    words: usize,
+    separator: &str,
+    capitalize: bool,
+    append_digit: bool,
+    wordlist: Option<&str>,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<(String, f64)> {
+    if words == 0 || words > MAX_PASSPHRASE_WORDS {
+        bail!(
+            "Number of words must be between 1 and {}",
+            MAX_PASSPHRASE_WORDS
+        );
+    }
+    let list = parse_wordlist(wordlist.unwrap_or(DEFAULT_WORDLIST))?;
+
+    let mut chosen = Vec::with_capacity(words);
+    for _ in 0..words {
+        let word = *list.choose(rng).expect("wordlist won't be empty");
+        chosen.push(if capitalize {
+            capitalize_word(word)
+        } else {
+            word.to_string()
+        });
+    }
+    let mut entropy = words as f64 * (list.len() as f64).log2();
+
+    if append_digit {
+        let digit = rng.gen_range(0..10);
+        chosen
+            .last_mut()
+            .expect("at least one word")
+            .push_str(&digit.to_string());
+        entropy += 10f64.log2();
+    }
+
+    Ok((chosen.join(separator), entropy))
+}
+
+fn parse_wordlist(content: &str) -> Result<Vec<&str>> {
+    let mut seen = HashSet::new();
+    let mut list = Vec::new();
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let word = match (fields.next(), fields.next()) {
+            (Some(dice), Some(word)) if dice.bytes().all(|c| c.is_ascii_digit()) => word,
+            (Some(word), None) => word,
+            (None, _) => continue,
+            _ => bail!("Invalid wordlist line: {:?}", line),
+        };
+        if seen.insert(word) {
+            list.push(word);
+        }
+    }
+    if list.len() < 2 {
+        bail!("Wordlist needs at least two distinct words");
+    }
+    Ok(list)
+}
+
+fn capitalize_word(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_passphrase() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        // some EFF words such as "yo-yo" contain the default separator
+        let (phrase, entropy) = process_passphrase(6, " ", true, true, None, &mut rng)?;
+        let words: Vec<&str> = phrase.split(' ').collect();
+        assert_eq!(words.len(), 6);
+        assert!(words.iter().all(|w| w.starts_with(char::is_uppercase)));
+        assert!(phrase.ends_with(|c: char| c.is_ascii_digit()));
+        assert!((entropy - (6.0 * 7776f64.log2() + 10f64.log2())).abs() < 1e-9);
+        assert!(entropy > 80.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_passphrase_eff_wordlist() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let wordlist = "11111\tabacus\n11112\tabdomen\n11113\tabdominal\n11114\tabide\n";
+        let (phrase, entropy) = process_passphrase(3, " ", false, false, Some(wordlist), &mut rng)?;
+        assert!(
+            phrase
+                .split(' ')
+                .all(|w| ["abacus", "abdomen", "abdominal", "abide"].contains(&w))
+        );
+        assert_eq!(entropy, 6.0);
+
+        assert!(process_passphrase(3, " ", false, false, Some("one\none\n"), &mut rng).is_err());
+        assert!(process_passphrase(3, " ", false, false, Some("a b c\n"), &mut rng).is_err());
+        assert!(process_passphrase(0, " ", false, false, None, &mut rng).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+use super::gen_pass::{MAX_PASSWORD_LENGTH, SYMBOL};
+use anyhow::{Result, bail};
+use rand::{CryptoRng, RngCore, seq::SliceRandom};
+
+const CONSONANTS: &str = "bcdfghjklmnpqrstvwxyz";
+const VOWELS: &str = "aeiou";
+const DIGITS: &str = "0123456789";
+
+enum Token {
+    Class(Vec<char>),
+    Literal(char),
+}
+
+/// Generate a password shaped by `pattern` and return it with its entropy in bits.
+///
+/// Placeholders: `C`/`c` upper/lower consonant, `V`/`v` upper/lower vowel, `9` digit,
+/// `S` symbol. `\x` escapes a placeholder, `{n}` repeats the previous token n times
+/// and anything else is copied literally, e.g. `Cvcc-9{4}-SSS`.
+pub fn process_genpass_pattern(
+    pattern: &str,
+    symbols_set: Option<&str>,
+    exclude: &str,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<(String, f64)> {
+    let symbols = symbols_set.unwrap_or(std::str::from_utf8(SYMBOL)?);
+    let tokens = parse_pattern(pattern, symbols, exclude)?;
+
+    let mut password = String::with_capacity(tokens.len());
+    let mut entropy = 0.0;
+    for token in &tokens {
+        match token {
+            Token::Class(chars) => {
+                password.push(*chars.choose(rng).expect("class won't be empty"));
+                entropy += (chars.len() as f64).log2();
+            }
+            Token::Literal(c) => password.push(*c),
+        }
+    }
+    Ok((password, entropy))
+}
+
+fn parse_pattern(pattern: &str, symbols: &str, exclude: &str) -> Result<Vec<Token>> {
+    let class = |set: &str, upper: bool, name: &str| -> Result<Token> {
+        let chars: Vec<char> = set
+            .chars()
+            .map(|c| if upper { c.to_ascii_uppercase() } else { c })
+            .filter(|&c| !exclude.contains(c))
+            .collect();
+        if chars.is_empty() {
+            bail!("No {} characters left after exclusions", name);
+        }
+        Ok(Token::Class(chars))
+    };
+
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            'C' => class(CONSONANTS, true, "consonant")?,
+            'c' => class(CONSONANTS, false, "consonant")?,
+            'V' => class(VOWELS, true, "vowel")?,
+            'v' => class(VOWELS, false, "vowel")?,
+            '9' => class(DIGITS, false, "digit")?,
+            'S' => class(symbols, false, "symbol")?,
+            '\\' => match chars.next() {
+                Some(c) => Token::Literal(c),
+                None => bail!("Pattern ends with a dangling escape"),
+            },
+            '{' => {
+                let mut count = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    count.push(c);
+                }
+                if !closed {
+                    bail!("Unterminated repetition: {{{}", count);
+                }
+                let Ok(count) = count.parse::<usize>() else {
+                    bail!("Invalid repetition count: {{{}}}", count);
+                };
+                let repeated = match tokens.pop() {
+                    Some(token) => token,
+                    None => bail!("Repetition without a preceding placeholder"),
+                };
+                if count == 0 || tokens.len() + count > MAX_PASSWORD_LENGTH {
+                    bail!(
+                        "Repetition count must be between 1 and {}",
+                        MAX_PASSWORD_LENGTH
+                    );
+                }
+                for _ in 1..count {
+                    tokens.push(match &repeated {
+                        Token::Class(chars) => Token::Class(chars.clone()),
+                        Token::Literal(c) => Token::Literal(*c),
+                    });
+                }
+                repeated
+            }
+            c => Token::Literal(c),
+        };
+        tokens.push(token);
+    }
+
+    if tokens.is_empty() {
+        bail!("Pattern is empty");
+    }
+    if tokens.len() > MAX_PASSWORD_LENGTH {
+        bail!(
+            "Pattern exceeds the maximum length of {}",
+            MAX_PASSWORD_LENGTH
+        );
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_genpass_pattern() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let (ret, entropy) = process_genpass_pattern("Cvcc-9{4}-SSS", None, "", &mut rng)?;
+        let chars: Vec<char> = ret.chars().collect();
+        assert_eq!(chars.len(), 13);
+        assert!(CONSONANTS.contains(chars[0].to_ascii_lowercase()) && chars[0].is_uppercase());
+        assert!(VOWELS.contains(chars[1]));
+        assert_eq!(chars[4], '-');
+        assert!(chars[5..9].iter().all(char::is_ascii_digit));
+        assert_eq!(chars[9], '-');
+        assert!(chars[10..].iter().all(|&c| SYMBOL.contains(&(c as u8))));
+
+        let expected = 3.0 * 21f64.log2() + 5f64.log2() + 4.0 * 10f64.log2() + 3.0 * 9f64.log2();
+        assert!((entropy - expected).abs() < 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_genpass_pattern_escapes_and_errors() -> Result<()> {
+        let mut rng = rand::thread_rng();
+        let (ret, entropy) = process_genpass_pattern(r"\C\9x{3}\{", None, "", &mut rng)?;
+        assert_eq!(ret, "C9xxx{");
+        assert_eq!(entropy, 0.0);
+
+        let (ret, _) = process_genpass_pattern("9{8}", None, "012345678", &mut rng)?;
+        assert_eq!(ret, "99999999");
+
+        for pattern in ["", "{3}", "9{x}", "9{0}", "9{99999}", "abc\\", "9{4"] {
+            assert!(
+                process_genpass_pattern(pattern, None, "", &mut rng).is_err(),
+                "{pattern}"
+            );
+        }
+        assert!(process_genpass_pattern("v", None, "aeiou", &mut rng).is_err());
+        Ok(())
+    }
+}
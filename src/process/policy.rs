@@ -0,0 +1,301 @@
+use super::gen_pass::{ClassPredicate, GenPassConfig, MAX_PASSWORD_LENGTH, SYMBOL};
+use anyhow::{Result, anyhow, bail};
+use serde::Deserialize;
+use std::collections::HashMap;
+use zxcvbn::zxcvbn;
+
+/// Give up on generating a compliant password after this many candidates.
+const MAX_ATTEMPTS: usize = 1000;
+
+/// A named password policy from a YAML or TOML policy file.
+///
+/// Disabled classes, excluded characters and symbols outside `symbols_set` are
+/// rejected when checking existing passwords, since the target system would
+/// refuse them as well.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    /// Length of generated passwords, defaults to 16 within the range
+    pub length: Option<usize>,
+    pub upper: bool,
+    pub lower: bool,
+    pub numbers: bool,
+    pub symbols: bool,
+    pub symbols_set: Option<String>,
+    pub exclude: String,
+    pub min_upper: usize,
+    pub min_lower: usize,
+    pub min_digits: usize,
+    pub min_symbols: usize,
+    /// Minimum zxcvbn score (0-4)
+    pub min_score: u8,
+    /// Words that must not appear in the password, in any case
+    pub banned_words: Vec<String>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 1,
+            max_length: MAX_PASSWORD_LENGTH,
+            length: None,
+            upper: true,
+            lower: true,
+            numbers: true,
+            symbols: true,
+            symbols_set: None,
+            exclude: String::new(),
+            min_upper: 0,
+            min_lower: 0,
+            min_digits: 0,
+            min_symbols: 0,
+            min_score: 0,
+            banned_words: Vec::new(),
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Generator settings that satisfy the policy's length and classes.
+    pub fn config(&self) -> GenPassConfig {
+        GenPassConfig {
+            length: self
+                .length
+                .unwrap_or(16)
+                .clamp(self.min_length, self.max_length),
+            upper: self.upper,
+            lower: self.lower,
+            number: self.numbers,
+            symbol: self.symbols,
+            charset: None,
+            symbols_set: self.symbols_set.clone(),
+            exclude: self.exclude.clone(),
+            min_upper: self.min_upper,
+            min_lower: self.min_lower,
+            min_number: self.min_digits,
+            min_symbol: self.min_symbols,
+        }
+    }
+
+    /// Every rule `password` breaks, empty when it complies. The messages don't
+    /// quote the password, its length or its characters, so they can go into a
+    /// shared report.
+    pub fn violations(&self, password: &str) -> Vec<String> {
+        let mut ret = Vec::new();
+        let length = password.chars().count();
+        if length < self.min_length || length > self.max_length {
+            ret.push(format!(
+                "length is outside {}..={}",
+                self.min_length, self.max_length
+            ));
+        }
+
+        let symbols = self
+            .symbols_set
+            .as_deref()
+            .unwrap_or(std::str::from_utf8(SYMBOL).expect("symbols are ascii"));
+        let classes: [(&str, bool, usize, ClassPredicate); 4] = [
+            (
+                "uppercase",
+                self.upper,
+                self.min_upper,
+                char::is_ascii_uppercase,
+            ),
+            (
+                "lowercase",
+                self.lower,
+                self.min_lower,
+                char::is_ascii_lowercase,
+            ),
+            (
+                "digits",
+                self.numbers,
+                self.min_digits,
+                char::is_ascii_digit,
+            ),
+            ("symbols", self.symbols, self.min_symbols, |c| {
+                !c.is_ascii_alphanumeric()
+            }),
+        ];
+        for (name, enabled, min, predicate) in classes {
+            let count = password.chars().filter(predicate).count();
+            if !enabled && count > 0 {
+                ret.push(format!("{name} are not allowed"));
+            } else if count < min {
+                ret.push(format!("needs at least {min} {name}"));
+            }
+        }
+        if self.symbols
+            && self.symbols_set.is_some()
+            && password
+                .chars()
+                .any(|c| !c.is_ascii_alphanumeric() && !symbols.contains(c))
+        {
+            ret.push("has symbols outside the allowed set".to_string());
+        }
+        if password.chars().any(|c| self.exclude.contains(c)) {
+            ret.push("has excluded characters".to_string());
+        }
+
+        let lowered = password.to_lowercase();
+        for word in &self.banned_words {
+            if lowered.contains(&word.to_lowercase()) {
+                ret.push(format!("contains the banned word {word:?}"));
+            }
+        }
+
+        if self.min_score > 0 {
+            let banned: Vec<&str> = self.banned_words.iter().map(String::as_str).collect();
+            let score = u8::from(zxcvbn(password, &banned).score());
+            if score < self.min_score {
+                ret.push(format!(
+                    "strength score {} is below {}",
+                    score, self.min_score
+                ));
+            }
+        }
+        ret
+    }
+}
+
+/// Read the policy called `name` from a policy file, a map from policy names
+/// to their rules in YAML or, with `toml` set, TOML.
+pub fn load_policy(content: &str, toml: bool, name: &str) -> Result<PasswordPolicy> {
+    let mut policies: HashMap<String, PasswordPolicy> = if toml {
+        toml::from_str(content)?
+    } else {
+        serde_yaml::from_str(content)?
+    };
+    let policy = policies.remove(name).ok_or_else(|| {
+        let mut names: Vec<&String> = policies.keys().collect();
+        names.sort();
+        anyhow!("No policy named {:?}, the file defines {:?}", name, names)
+    })?;
+    if policy.min_length == 0 || policy.min_length > policy.max_length {
+        bail!("Policy {:?} has an empty length range", name);
+    }
+    if policy.max_length > MAX_PASSWORD_LENGTH {
+        bail!(
+            "Policy {:?} allows more than {} characters",
+            name,
+            MAX_PASSWORD_LENGTH
+        );
+    }
+    if policy.min_score > 4 {
+        bail!("Policy {:?} has a min_score above 4", name);
+    }
+    for (class, enabled, min, min_name) in [
+        ("upper", policy.upper, policy.min_upper, "min_upper"),
+        ("lower", policy.lower, policy.min_lower, "min_lower"),
+        ("numbers", policy.numbers, policy.min_digits, "min_digits"),
+        ("symbols", policy.symbols, policy.min_symbols, "min_symbols"),
+    ] {
+        if !enabled && min > 0 {
+            bail!("Policy {:?} sets {} but disables {}", name, min_name, class);
+        }
+    }
+    Ok(policy)
+}
+
+/// Call `generate` until its password complies with `policy`.
+pub fn process_policy_enforce(
+    mut generate: impl FnMut() -> Result<String>,
+    policy: &PasswordPolicy,
+) -> Result<String> {
+    for _ in 0..MAX_ATTEMPTS {
+        let password = generate()?;
+        if policy.violations(&password).is_empty() {
+            return Ok(password);
+        }
+    }
+    bail!(
+        "Could not generate a password for this policy in {} attempts",
+        MAX_ATTEMPTS
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_rng;
+    use crate::process::process_genpass_config;
+
+    const POLICIES: &str = include_str!("../../fixtures/policies.yaml");
+
+    #[test]
+    fn test_load_policy() -> Result<()> {
+        let policy = load_policy(POLICIES, false, "corp-ad")?;
+        assert_eq!(policy.min_length, 14);
+        assert_eq!(policy.config().length, 16);
+        assert_eq!(policy.banned_words, ["acme", "winter"]);
+
+        let policy = load_policy(POLICIES, false, "legacy-mainframe")?;
+        assert!(!policy.symbols);
+        assert_eq!(policy.config().length, 8);
+
+        let toml = "[pin]\nmin_length = 6\nmax_length = 6\nupper = false\nlower = false\nsymbols = false\n";
+        let policy = load_policy(toml, true, "pin")?;
+        assert_eq!(policy.config().length, 6);
+
+        assert!(load_policy(POLICIES, false, "missing").is_err());
+        assert!(load_policy("x:\n  lenght: 3\n", false, "x").is_err());
+        assert!(load_policy("x:\n  min_length: 9\n  max_length: 8\n", false, "x").is_err());
+        let err = load_policy("x:\n  upper: false\n  min_upper: 1\n", false, "x").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Policy \"x\" sets min_upper but disables upper"
+        );
+        assert!(load_policy("x:\n  symbols: false\n  min_symbols: 2\n", false, "x").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy_violations() -> Result<()> {
+        let policy = load_policy(POLICIES, false, "corp-ad")?;
+        assert!(policy.violations("k7#Vq9!mZr2@xWp4").is_empty());
+
+        let violations = policy.violations("AcmeWinter2024");
+        assert!(
+            violations
+                .iter()
+                .any(|v| v.contains("banned word \"acme\""))
+        );
+        assert!(
+            violations
+                .iter()
+                .any(|v| v.contains("banned word \"winter\""))
+        );
+        assert!(violations.iter().any(|v| v.contains("symbols")));
+
+        let violations = policy.violations("Password1234!A");
+        assert_eq!(violations, ["strength score 2 is below 3"]);
+
+        let policy = load_policy(POLICIES, false, "legacy-mainframe")?;
+        let violations = policy.violations("abc!");
+        assert_eq!(
+            violations,
+            [
+                "length is outside 6..=8",
+                "lowercase are not allowed",
+                "needs at least 2 digits",
+                "symbols are not allowed",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_policy_enforce() -> Result<()> {
+        let mut rng = get_rng(Some(11));
+        for name in ["corp-ad", "legacy-mainframe"] {
+            let policy = load_policy(POLICIES, false, name)?;
+            let config = policy.config();
+            let ret =
+                process_policy_enforce(|| process_genpass_config(&config, &mut rng), &policy)?;
+            assert!(policy.violations(&ret).is_empty(), "{name}: {ret}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,257 @@
+use super::gen_pass::{GenPassConfig, MAX_PASSWORD_LENGTH, NUMBER, SYMBOL};
+use anyhow::{Result, anyhow, bail};
+use rand::{CryptoRng, RngCore, seq::SliceRandom};
+
+// l and y are left out, they are easy to mishear or misspell over the phone
+const CONSONANTS: &str = "bcdfghjkmnprstvwz";
+const VOWELS: &str = "aeiou";
+
+/// Generate a password of alternating consonants and vowels, e.g. `Tobagure47!`,
+/// and return it with its entropy in bits.
+///
+/// Enabled digit and symbol classes are appended after the letters, with at
+/// least one character each (or their `min_*`). With uppercase enabled, `min_upper`
+/// letters (at least one) are capitalized at random positions and at least
+/// `min_lower` are left lowercase; with lowercase disabled all letters are
+/// uppercase. The entropy counts exactly these choices,
+/// so it is much lower than for a random password of the same length.
+pub fn process_genpass_pronounceable(
+    config: &GenPassConfig,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<(String, f64)> {
+    if config.charset.is_some() {
+        bail!("Pronounceable passwords use the built-in classes, not a charset");
+    }
+    if !config.upper && !config.lower {
+        bail!("Pronounceable passwords need uppercase or lowercase letters");
+    }
+    if config.length > MAX_PASSWORD_LENGTH {
+        bail!(
+            "Password length {} exceeds the maximum of {}",
+            config.length,
+            MAX_PASSWORD_LENGTH
+        );
+    }
+
+    // a letter may end up capitalized, so excluding either case drops it
+    let excluded = |c: char| {
+        config.exclude.contains(c)
+            || (config.upper && config.exclude.contains(c.to_ascii_uppercase()))
+    };
+    let filter = |set: &str| -> Vec<char> { set.chars().filter(|&c| !excluded(c)).collect() };
+    let consonants = filter(CONSONANTS);
+    let vowels = filter(VOWELS);
+    if consonants.is_empty() || vowels.is_empty() {
+        bail!("No consonants or vowels left after exclusions");
+    }
+    let symbols = config
+        .symbols_set
+        .as_deref()
+        .unwrap_or(std::str::from_utf8(SYMBOL)?);
+    let tails = [
+        (
+            config.number,
+            config.min_number,
+            "digits",
+            std::str::from_utf8(NUMBER)?,
+        ),
+        (config.symbol, config.min_symbol, "symbols", symbols),
+    ];
+
+    // check the length before drawing anything, the minimums come from the user
+    let mut tail_len: usize = 0;
+    for (enabled, min, name, _) in &tails {
+        if !enabled {
+            if *min > 0 {
+                bail!("--min-{} requires that class to be enabled", name);
+            }
+            continue;
+        }
+        tail_len = tail_len
+            .checked_add((*min).max(1))
+            .ok_or_else(|| anyhow!("The --min-* counts add up to more than any password length"))?;
+    }
+    for (enabled, min, name) in [
+        (config.upper, config.min_upper, "upper"),
+        (config.lower, config.min_lower, "lower"),
+    ] {
+        if !enabled && min > 0 {
+            bail!("--min-{} requires that class to be enabled", name);
+        }
+    }
+    let upper_count = if config.upper && config.lower {
+        config.min_upper.max(1)
+    } else {
+        0
+    };
+    // with a single letter case every letter counts towards its minimum
+    let letters_required = if config.upper && config.lower {
+        upper_count.saturating_add(config.min_lower)
+    } else {
+        config.min_upper.max(config.min_lower)
+    };
+    let required = tail_len.saturating_add(letters_required.max(1));
+    if config.length < required {
+        bail!(
+            "Password length {} is too short for the {} characters required by the enabled classes",
+            config.length,
+            required
+        );
+    }
+
+    let mut tail = String::new();
+    let mut entropy = 0.0;
+    for (enabled, min, name, set) in tails {
+        if !enabled {
+            continue;
+        }
+        let chars = filter(set);
+        if chars.is_empty() {
+            bail!("No {} characters left after exclusions", name);
+        }
+        for _ in 0..min.max(1) {
+            tail.push(*chars.choose(rng).expect("class won't be empty"));
+        }
+        entropy += min.max(1) as f64 * (chars.len() as f64).log2();
+    }
+
+    let letters_len = config.length - tail_len;
+    let mut letters: Vec<char> = Vec::with_capacity(letters_len);
+    for i in 0..letters_len {
+        let set = if i % 2 == 0 { &consonants } else { &vowels };
+        letters.push(*set.choose(rng).expect("class won't be empty"));
+        entropy += (set.len() as f64).log2();
+    }
+
+    if config.upper && !config.lower {
+        letters.iter_mut().for_each(|c| *c = c.to_ascii_uppercase());
+    } else if upper_count > 0 {
+        let positions = rand::seq::index::sample(rng, letters_len, upper_count);
+        for i in positions {
+            letters[i] = letters[i].to_ascii_uppercase();
+        }
+        entropy += log2_binomial(letters_len, upper_count);
+    }
+
+    Ok((letters.into_iter().chain(tail.chars()).collect(), entropy))
+}
+
+/// log2 of "n choose k", the number of ways to pick the capitalized letters.
+fn log2_binomial(n: usize, k: usize) -> f64 {
+    (0..k)
+        .map(|i| ((n - i) as f64).log2() - ((i + 1) as f64).log2())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_rng;
+
+    #[test]
+    fn test_process_genpass_pronounceable() -> Result<()> {
+        let mut rng = get_rng(Some(5));
+        let config = GenPassConfig {
+            length: 12,
+            ..Default::default()
+        };
+        let (ret, entropy) = process_genpass_pronounceable(&config, &mut rng)?;
+        let chars: Vec<char> = ret.chars().collect();
+        assert_eq!(chars.len(), 12);
+        for (i, c) in chars[..10].iter().enumerate() {
+            let set = if i % 2 == 0 { CONSONANTS } else { VOWELS };
+            assert!(set.contains(c.to_ascii_lowercase()), "{ret}");
+        }
+        assert_eq!(chars[..10].iter().filter(|c| c.is_uppercase()).count(), 1);
+        assert!(NUMBER.contains(&(chars[10] as u8)));
+        assert!(SYMBOL.contains(&(chars[11] as u8)));
+
+        let expected =
+            5.0 * 17f64.log2() + 5.0 * 5f64.log2() + 10f64.log2() + 9f64.log2() + 9f64.log2();
+        assert!((entropy - expected).abs() < 1e-9, "{entropy} {expected}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_genpass_pronounceable_rejects_impossible_configs() {
+        let mut rng = get_rng(Some(5));
+        let configs = [
+            GenPassConfig {
+                charset: Some("abc".to_string()),
+                ..Default::default()
+            },
+            GenPassConfig {
+                upper: false,
+                lower: false,
+                ..Default::default()
+            },
+            GenPassConfig {
+                length: 2,
+                ..Default::default()
+            },
+            GenPassConfig {
+                exclude: "aeiou".to_string(),
+                ..Default::default()
+            },
+            GenPassConfig {
+                min_number: 50_000_000,
+                ..Default::default()
+            },
+            GenPassConfig {
+                min_number: usize::MAX,
+                ..Default::default()
+            },
+            GenPassConfig {
+                length: 12,
+                min_upper: 5,
+                min_lower: 6,
+                ..Default::default()
+            },
+        ];
+        for config in configs {
+            assert!(
+                process_genpass_pronounceable(&config, &mut rng).is_err(),
+                "{config:?}"
+            );
+        }
+
+        for (config, class) in [
+            (
+                GenPassConfig {
+                    upper: false,
+                    min_upper: 1,
+                    ..Default::default()
+                },
+                "upper",
+            ),
+            (
+                GenPassConfig {
+                    lower: false,
+                    min_lower: 1,
+                    ..Default::default()
+                },
+                "lower",
+            ),
+        ] {
+            let err = process_genpass_pronounceable(&config, &mut rng).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("--min-{class} requires that class to be enabled")
+            );
+        }
+    }
+
+    #[test]
+    fn test_process_genpass_pronounceable_min_lower() -> Result<()> {
+        let mut rng = get_rng(Some(5));
+        let config = GenPassConfig {
+            length: 12,
+            min_upper: 4,
+            min_lower: 6,
+            ..Default::default()
+        };
+        let (ret, _) = process_genpass_pronounceable(&config, &mut rng)?;
+        assert_eq!(ret.chars().filter(char::is_ascii_lowercase).count(), 6);
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+use super::gen_pass::GenPassConfig;
+use crate::cli::SiteKdf;
+use anyhow::{Result, anyhow};
+use argon2::{Algorithm, Argon2, Params, Version};
+use blake3::OutputReader;
+
+/// blake3 `derive_key` context, changing it changes every derived password.
+const CONTEXT: &str = "rstool 2026-10-18 site password v1";
+
+// Argon2id cost, pinned so upstream default changes can't alter the output.
+const ARGON2_MEMORY_KIB: u32 = 19 * 1024;
+const ARGON2_ITERATIONS: u32 = 2;
+const ARGON2_PARALLELISM: u32 = 1;
+
+/// Derive the password for `site` and `login` from `master`, LessPass style.
+///
+/// Nothing is stored: the same inputs always give the same password, and
+/// bumping `counter` rotates it. The same charset rules as `genpass` apply,
+/// but characters are drawn from the blake3 output keyed with the derived key
+/// rather than from a `rand` generator, so a `rand` upgrade can't change it:
+/// each class minimum in order, then the rest from the whole pool, then a
+/// Fisher-Yates shuffle, all using `uniform`.
+pub fn process_site_password(
+    config: &GenPassConfig,
+    master: &[u8],
+    site: &str,
+    login: &str,
+    counter: u32,
+    kdf: SiteKdf,
+) -> Result<String> {
+    let mut material = Vec::new();
+    for part in [site.as_bytes(), login.as_bytes()] {
+        material.extend_from_slice(&(part.len() as u64).to_be_bytes());
+        material.extend_from_slice(part);
+    }
+    material.extend_from_slice(&counter.to_be_bytes());
+
+    let seed = match kdf {
+        SiteKdf::Blake3 => {
+            let mut key_material = (master.len() as u64).to_be_bytes().to_vec();
+            key_material.extend_from_slice(master);
+            key_material.extend_from_slice(&material);
+            blake3::derive_key(CONTEXT, &key_material)
+        }
+        SiteKdf::Argon2 => {
+            let salt = blake3::derive_key(CONTEXT, &material);
+            let params = Params::new(
+                ARGON2_MEMORY_KIB,
+                ARGON2_ITERATIONS,
+                ARGON2_PARALLELISM,
+                Some(32),
+            )
+            .map_err(|e| anyhow!("{e}"))?;
+            let mut seed = [0u8; 32];
+            Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                .hash_password_into(master, &salt, &mut seed)
+                .map_err(|e| anyhow!("{e}"))?;
+            seed
+        }
+    };
+
+    let (pool, classes) = config.checked_classes()?;
+    let mut stream = blake3::Hasher::new_keyed(&seed).finalize_xof();
+    let mut password = Vec::with_capacity(config.length);
+    for class in &classes {
+        for _ in 0..class.min {
+            password.push(class.chars[uniform(&mut stream, class.chars.len())]);
+        }
+    }
+    while password.len() < config.length {
+        password.push(pool[uniform(&mut stream, pool.len())]);
+    }
+    for i in (1..password.len()).rev() {
+        password.swap(i, uniform(&mut stream, i + 1));
+    }
+    Ok(password.into_iter().collect())
+}
+
+/// An index below `n` from the next 4-byte big-endian words of `stream`,
+/// rejecting words past the largest multiple of `n` so there is no modulo bias.
+fn uniform(stream: &mut OutputReader, n: usize) -> usize {
+    let n = n as u64;
+    let zone = (1u64 << 32) / n * n;
+    loop {
+        let mut word = [0u8; 4];
+        stream.fill(&mut word);
+        let x = u32::from_be_bytes(word) as u64;
+        if x < zone {
+            return (x % n) as usize;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_site_password_known_answers() -> Result<()> {
+        // the blake3 answers were checked against an independent Python port of
+        // the BLAKE3 reference implementation and of the sampling above
+        let config = GenPassConfig::default();
+        let master = b"correct horse battery staple";
+        let ret =
+            process_site_password(&config, master, "example.com", "alice", 1, SiteKdf::Blake3)?;
+        assert_eq!(ret, "hm6g3N_QyuVL798i");
+        let ret =
+            process_site_password(&config, master, "example.com", "alice", 2, SiteKdf::Blake3)?;
+        assert_eq!(ret, "jiimkpPaz8a2K#FL");
+        let ret =
+            process_site_password(&config, master, "example.org", "alice", 1, SiteKdf::Blake3)?;
+        assert_eq!(ret, "v3yqHmN9Z&kQ!kAZ");
+        let ret =
+            process_site_password(&config, master, "example.com", "alice", 1, SiteKdf::Argon2)?;
+        assert_eq!(ret, "Bi3J2W*146h$RnBN");
+
+        let config = GenPassConfig {
+            length: 8,
+            symbol: false,
+            ..Default::default()
+        };
+        let ret = process_site_password(&config, master, "example.com", "bob", 1, SiteKdf::Blake3)?;
+        assert_eq!(ret, "cypW7cvu");
+        Ok(())
+    }
+
+    #[test]
+    fn test_process_site_password_separates_fields() -> Result<()> {
+        let config = GenPassConfig::default();
+        let a = process_site_password(&config, b"m", "ab", "c", 1, SiteKdf::Blake3)?;
+        let b = process_site_password(&config, b"m", "a", "bc", 1, SiteKdf::Blake3)?;
+        assert_ne!(a, b);
+        Ok(())
+    }
+}
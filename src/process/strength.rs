@@ -0,0 +1,176 @@
+use anyhow::{Result, bail};
+use serde::Serialize;
+use std::fmt;
+use zxcvbn::matching::patterns::MatchPattern;
+use zxcvbn::zxcvbn;
+
+/// Give up on `--min-score` after this many candidates.
+const MAX_ATTEMPTS: usize = 1000;
+
+/// zxcvbn estimate of a password in a form that can be printed or serialized.
+#[derive(Debug, Serialize)]
+pub struct StrengthReport {
+    pub score: u8,
+    pub guesses: u64,
+    pub guesses_log10: f64,
+    pub crack_times: CrackTimesReport,
+    pub warning: Option<String>,
+    pub suggestions: Vec<String>,
+    pub patterns: Vec<PatternReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CrackTimesReport {
+    pub online_throttling_100_per_hour: String,
+    pub online_no_throttling_10_per_second: String,
+    pub offline_slow_hashing_1e4_per_second: String,
+    pub offline_fast_hashing_1e10_per_second: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PatternReport {
+    pub pattern: &'static str,
+    pub token: String,
+    pub guesses: Option<u64>,
+}
+
+pub fn process_strength(password: &str, user_inputs: &[&str]) -> StrengthReport {
+    let estimate = zxcvbn(password, user_inputs);
+    let crack_times = estimate.crack_times();
+    let feedback = estimate.feedback();
+
+    StrengthReport {
+        score: estimate.score().into(),
+        guesses: estimate.guesses(),
+        guesses_log10: estimate.guesses_log10(),
+        crack_times: CrackTimesReport {
+            online_throttling_100_per_hour: crack_times
+                .online_throttling_100_per_hour()
+                .to_string(),
+            online_no_throttling_10_per_second: crack_times
+                .online_no_throttling_10_per_second()
+                .to_string(),
+            offline_slow_hashing_1e4_per_second: crack_times
+                .offline_slow_hashing_1e4_per_second()
+                .to_string(),
+            offline_fast_hashing_1e10_per_second: crack_times
+                .offline_fast_hashing_1e10_per_second()
+                .to_string(),
+        },
+        warning: feedback.and_then(|f| f.warning()).map(|w| w.to_string()),
+        suggestions: feedback
+            .map(|f| f.suggestions().iter().map(|s| s.to_string()).collect())
+            .unwrap_or_default(),
+        patterns: estimate
+            .sequence()
+            .iter()
+            .map(|m| PatternReport {
+                pattern: pattern_name(&m.pattern),
+                token: m.token.clone(),
+                guesses: m.guesses,
+            })
+            .collect(),
+    }
+}
+
+/// Call `generate` until its password reaches `min_score`.
+pub fn process_min_score(
+    mut generate: impl FnMut() -> Result<String>,
+    min_score: u8,
+    user_inputs: &[&str],
+) -> Result<String> {
+    for _ in 0..MAX_ATTEMPTS {
+        let password = generate()?;
+        if u8::from(zxcvbn(&password, user_inputs).score()) >= min_score {
+            return Ok(password);
+        }
+    }
+    bail!(
+        "Could not reach a strength score of {} in {} attempts, try a longer password",
+        min_score,
+        MAX_ATTEMPTS
+    )
+}
+
+fn pattern_name(pattern: &MatchPattern) -> &'static str {
+    match pattern {
+        MatchPattern::Dictionary(_) => "dictionary",
+        MatchPattern::Spatial(_) => "spatial",
+        MatchPattern::Repeat(_) => "repeat",
+        MatchPattern::Sequence(_) => "sequence",
+        MatchPattern::Regex(_) => "regex",
+        MatchPattern::Date(_) => "date",
+        MatchPattern::BruteForce => "bruteforce",
+    }
+}
+
+impl fmt::Display for StrengthReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Score: {}/4", self.score)?;
+        writeln!(
+            f,
+            "Guesses: {} (10^{:.1})",
+            self.guesses, self.guesses_log10
+        )?;
+        writeln!(f, "Crack times:")?;
+        let times = &self.crack_times;
+        writeln!(
+            f,
+            "  online, throttled (100/hour):   {}",
+            times.online_throttling_100_per_hour
+        )?;
+        writeln!(
+            f,
+            "  online, unthrottled (10/s):     {}",
+            times.online_no_throttling_10_per_second
+        )?;
+        writeln!(
+            f,
+            "  offline, slow hash (1e4/s):     {}",
+            times.offline_slow_hashing_1e4_per_second
+        )?;
+        writeln!(
+            f,
+            "  offline, fast hash (1e10/s):    {}",
+            times.offline_fast_hashing_1e10_per_second
+        )?;
+        writeln!(f, "Patterns:")?;
+        for p in &self.patterns {
+            writeln!(f, "  {:<10} {:?}", p.pattern, p.token)?;
+        }
+        if let Some(warning) = &self.warning {
+            writeln!(f, "Warning: {warning}")?;
+        }
+        for suggestion in &self.suggestions {
+            writeln!(f, "Suggestion: {suggestion}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_strength() {
+        let report = process_strength("password1", &[]);
+        assert!(report.score <= 1);
+        assert!(report.warning.is_some());
+        assert_eq!(report.patterns[0].pattern, "dictionary");
+
+        let weak = process_strength("juventus1897", &["juventus"]);
+        let strong = process_strength("juventus1897", &[]);
+        assert!(weak.guesses <= strong.guesses);
+    }
+
+    #[test]
+    fn test_process_min_score() -> Result<()> {
+        let mut candidates = ["password", "qwerty", "Tr0ub4dor&3-horse-battery"].into_iter();
+        let ret = process_min_score(|| Ok(candidates.next().unwrap().to_string()), 3, &[])?;
+        assert_eq!(ret, "Tr0ub4dor&3-horse-battery");
+
+        assert!(process_min_score(|| Ok("password".to_string()), 4, &[]).is_err());
+        Ok(())
+    }
+}
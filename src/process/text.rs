@@ -4,7 +4,7 @@ use crate::TextSignFormat;
 use crate::process::process_genpass;
 use anyhow::Result;
 use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
-use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
 
 pub trait TextSigner {
     // signer could sign any input data
@@ -31,8 +31,8 @@ impl Blake3 {
         Ok(Self::new(key))
     }
 
-    fn generate() -> Result<HashMap<&'static str, Vec<u8>>> {
-        let key = process_genpass(32, true, true, true, true)?;
+    fn generate(rng: &mut (impl RngCore + CryptoRng)) -> Result<HashMap<&'static str, Vec<u8>>> {
+        let key = process_genpass(32, true, true, true, true, rng)?;
         let mut map = HashMap::new();
         map.insert("blake3.txt", key.as_bytes().to_vec());
         Ok(map)
@@ -55,9 +55,8 @@ impl Ed25519Signer {
         Ok(Self::new(key))
     }
 
-    fn generate() -> Result<HashMap<&'static str, Vec<u8>>> {
-        let mut csprng = OsRng;
-        let sk: SigningKey = SigningKey::generate(&mut csprng);
+    fn generate(rng: &mut (impl RngCore + CryptoRng)) -> Result<HashMap<&'static str, Vec<u8>>> {
+        let sk: SigningKey = SigningKey::generate(rng);
         let pk: VerifyingKey = (&sk).into();
 
         let mut map = HashMap::new();
@@ -144,10 +143,13 @@ pub fn process_text_verify(
     verifier.verify(reader, sig)
 }
 
-pub fn process_text_generate(format: TextSignFormat) -> Result<HashMap<&'static str, Vec<u8>>> {
+pub fn process_text_generate(
+    format: TextSignFormat,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<HashMap<&'static str, Vec<u8>>> {
     match format {
-        TextSignFormat::Blake3 => Blake3::generate(),
-        TextSignFormat::Ed25519 => Ed25519Signer::generate(),
+        TextSignFormat::Blake3 => Blake3::generate(rng),
+        TextSignFormat::Ed25519 => Ed25519Signer::generate(rng),
     }
 }
 
@@ -181,4 +183,21 @@ mod tests {
         assert!(ret);
         Ok(())
     }
+
+    #[test]
+    fn test_process_text_generate_is_reproducible() -> Result<()> {
+        let keys = process_text_generate(TextSignFormat::Ed25519, &mut crate::get_rng(Some(7)))?;
+        let again = process_text_generate(TextSignFormat::Ed25519, &mut crate::get_rng(Some(7)))?;
+        assert_eq!(keys, again);
+        assert_eq!(
+            URL_SAFE_NO_PAD.encode(&keys["ed25519.pk"]),
+            "EKGGDuAfoNrRdUO0H6VvTgmHCBAAGfX3zsH8WbLMD-w"
+        );
+
+        let signer = Ed25519Signer::try_new(&keys["ed25519.sk"])?;
+        let verifier = Ed25519Verifier::try_new(&keys["ed25519.pk"])?;
+        let sig = signer.sign(&mut "hello".as_bytes())?;
+        assert!(verifier.verify(&mut "hello".as_bytes(), &sig)?);
+        Ok(())
+    }
 }
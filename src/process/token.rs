@@ -0,0 +1,185 @@
+use super::b64::encode_bytes;
+use crate::cli::{Base64Format, TokenKind};
+use anyhow::Result;
+use rand::{CryptoRng, RngCore, seq::SliceRandom};
+use std::time::{SystemTime, UNIX_EPOCH};
+use ulid::Ulid;
+use uuid::Builder;
+
+/// Default nanoid alphabet, 64 url-safe characters.
+const NANOID_ALPHABET: &[u8] = b"_-0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Timestamp and random part of the last uuid7 or ulid, shared by the tokens
+/// of one batch so they sort in the order they were generated.
+#[derive(Debug, Default)]
+pub struct TokenClock {
+    last: Option<(u64, u128)>,
+}
+
+impl TokenClock {
+    /// Monotonic random (RFC 9562 method 2): within a millisecond the `bits`
+    /// wide random part is incremented instead of drawn again, and once it
+    /// runs out the timestamp moves a millisecond ahead.
+    fn next(
+        &mut self,
+        unix_ms: u64,
+        bits: u32,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> (u64, u128) {
+        let max = (1u128 << bits) - 1;
+        let mut fresh = || {
+            let mut random = [0u8; 16];
+            rng.fill_bytes(&mut random);
+            u128::from_be_bytes(random) & max
+        };
+        let next = match self.last {
+            Some((ms, random)) if unix_ms <= ms && random < max => (ms, random + 1),
+            Some((ms, _)) if unix_ms <= ms => (ms + 1, fresh()),
+            _ => (unix_ms, fresh()),
+        };
+        self.last = Some(next);
+        next
+    }
+}
+
+/// Generate a machine token. `bytes` sizes hex and b64url tokens, `length` is
+/// the number of nanoid characters; uuids and ulids have a fixed size and use
+/// `clock` to stay in order within a batch.
+pub fn process_token(
+    kind: TokenKind,
+    bytes: usize,
+    length: usize,
+    clock: &mut TokenClock,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<String> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+    Ok(token_at(kind, bytes, length, now, clock, rng))
+}
+
+fn token_at(
+    kind: TokenKind,
+    bytes: usize,
+    length: usize,
+    unix_ms: u64,
+    clock: &mut TokenClock,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> String {
+    match kind {
+        TokenKind::Hex => random_bytes(bytes, rng)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect(),
+        TokenKind::B64Url => encode_bytes(&random_bytes(bytes, rng), Base64Format::UrlSafe),
+        TokenKind::Uuid4 => {
+            let mut random = [0u8; 16];
+            rng.fill_bytes(&mut random);
+            Builder::from_random_bytes(random).into_uuid().to_string()
+        }
+        TokenKind::Uuid7 => {
+            // 12 bits of rand_a and 62 of rand_b, laid out so the version and
+            // variant bits the builder sets don't overwrite any of them
+            let (ms, random) = clock.next(unix_ms, 74, rng);
+            let mut bytes = [0u8; 10];
+            bytes[..2].copy_from_slice(&((random >> 62) as u16).to_be_bytes());
+            bytes[2..].copy_from_slice(&((random as u64) & (u64::MAX >> 2)).to_be_bytes());
+            Builder::from_unix_timestamp_millis(ms, &bytes)
+                .into_uuid()
+                .to_string()
+        }
+        TokenKind::Ulid => {
+            let (ms, random) = clock.next(unix_ms, 80, rng);
+            Ulid::from_parts(ms, random).to_string()
+        }
+        TokenKind::Nanoid => (0..length)
+            .map(|_| *NANOID_ALPHABET.choose(rng).expect("alphabet isn't empty") as char)
+            .collect(),
+    }
+}
+
+fn random_bytes(len: usize, rng: &mut (impl RngCore + CryptoRng)) -> Vec<u8> {
+    let mut buf = vec![0u8; len];
+    rng.fill_bytes(&mut buf);
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_rng;
+
+    const MS: u64 = 1_760_000_000_000;
+
+    #[test]
+    fn test_token_shapes() {
+        let mut rng = get_rng(Some(1));
+        let mut clock = TokenClock::default();
+        let hex = token_at(TokenKind::Hex, 32, 0, MS, &mut clock, &mut rng);
+        assert_eq!(hex.len(), 64);
+        assert!(hex.chars().all(|c| c.is_ascii_hexdigit()));
+
+        let b64 = token_at(TokenKind::B64Url, 32, 0, MS, &mut clock, &mut rng);
+        assert_eq!(b64.len(), 43);
+        assert!(!b64.contains(['+', '/', '=']));
+
+        let uuid = token_at(TokenKind::Uuid4, 0, 0, MS, &mut clock, &mut rng);
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "4");
+        assert!("89ab".contains(&uuid[19..20]));
+
+        let uuid = token_at(TokenKind::Uuid7, 0, 0, MS, &mut clock, &mut rng);
+        assert_eq!(&uuid[14..15], "7");
+        assert_eq!(&uuid[..13], "0199c82c-c000");
+
+        let ulid = token_at(TokenKind::Ulid, 0, 0, MS, &mut clock, &mut rng);
+        assert_eq!(ulid.len(), 26);
+        assert_eq!(ulid.parse::<Ulid>().unwrap().timestamp_ms(), MS);
+
+        let nanoid = token_at(TokenKind::Nanoid, 0, 21, MS, &mut clock, &mut rng);
+        assert_eq!(nanoid.len(), 21);
+        assert!(nanoid.bytes().all(|c| NANOID_ALPHABET.contains(&c)));
+    }
+
+    #[test]
+    fn test_token_is_reproducible_with_seed() {
+        let a = token_at(
+            TokenKind::Hex,
+            8,
+            0,
+            MS,
+            &mut TokenClock::default(),
+            &mut get_rng(Some(9)),
+        );
+        let b = token_at(
+            TokenKind::Hex,
+            8,
+            0,
+            MS,
+            &mut TokenClock::default(),
+            &mut get_rng(Some(9)),
+        );
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_time_ordered_tokens_are_sorted_within_a_batch() {
+        let mut rng = get_rng(Some(3));
+        for kind in [TokenKind::Uuid7, TokenKind::Ulid] {
+            let mut clock = TokenClock::default();
+            let batch: Vec<String> = (0..100)
+                .map(|_| token_at(kind, 0, 0, MS, &mut clock, &mut rng))
+                .collect();
+            assert!(batch.windows(2).all(|w| w[0] < w[1]), "{kind}");
+            // a clock that goes backwards doesn't break the order either
+            let earlier = token_at(kind, 0, 0, MS - 5, &mut clock, &mut rng);
+            assert!(batch[99] < earlier, "{kind}");
+        }
+
+        let mut clock = TokenClock {
+            last: Some((MS, (1 << 80) - 1)),
+        };
+        let ulid: Ulid = token_at(TokenKind::Ulid, 0, 0, MS, &mut clock, &mut rng)
+            .parse()
+            .unwrap();
+        assert_eq!(ulid.timestamp_ms(), MS + 1);
+    }
+}
@@ -0,0 +1,211 @@
+use anyhow::{Result, bail};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// XML 输出的元素命名和字段写法
+#[derive(Debug, Clone)]
+pub struct XmlOptions {
+    /// 根元素名
+    pub root: String,
+    /// 每条记录的元素名
+    pub row: String,
+    /// 字段写成记录元素的属性，而不是子元素
+    pub attributes: bool,
+}
+
+impl Default for XmlOptions {
+    fn default() -> Self {
+        Self {
+            root: "records".to_string(),
+            row: "record".to_string(),
+            attributes: false,
+        }
+    }
+}
+
+/// 将记录写成 XML，不是合法 XML 名字的表头会被转义（如 `Kit Number` 变为 `Kit_Number`）
+pub(crate) fn to_xml(ret: &[Value], opts: &XmlOptions) -> Result<String> {
+    for name in [&opts.root, &opts.row] {
+        if xml_name(name) != *name {
+            bail!("Invalid xml element name: {:?}", name);
+        }
+    }
+
+    let names = field_names(ret);
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    writeln!(xml, "<{}>", opts.root)?;
+    for record in ret {
+        let Value::Object(fields) = record else {
+            continue;
+        };
+        if opts.attributes {
+            write!(xml, "  <{}", opts.row)?;
+            for (k, v) in fields {
+                write!(xml, " {}=\"{}\"", names[k.as_str()], escape(&text(v), true))?;
+            }
+            xml.push_str("/>\n");
+        } else {
+            writeln!(xml, "  <{}>", opts.row)?;
+            for (k, v) in fields {
+                let name = &names[k.as_str()];
+                writeln!(xml, "    <{name}>{}</{name}>", escape(&text(v), false))?;
+            }
+            writeln!(xml, "  </{}>", opts.row)?;
+        }
+    }
+    writeln!(xml, "</{}>", opts.root)?;
+    Ok(xml)
+}
+
+/// 为每个表头生成唯一的 XML 名字，转义后重名的加上数字后缀
+fn field_names(ret: &[Value]) -> HashMap<&str, String> {
+    let mut names = HashMap::new();
+    let mut used = HashSet::new();
+    for key in ret
+        .iter()
+        .filter_map(Value::as_object)
+        .flat_map(|m| m.keys())
+    {
+        if names.contains_key(key.as_str()) {
+            continue;
+        }
+        let base = xml_name(key);
+        let mut name = base.clone();
+        let mut n = 2;
+        while !used.insert(name.clone()) {
+            name = format!("{base}_{n}");
+            n += 1;
+        }
+        names.insert(key.as_str(), name);
+    }
+    names
+}
+
+/// 非法字符替换为 `_`，不能作为开头的字符或以 `xml` 开头的名字前加 `_`
+fn xml_name(s: &str) -> String {
+    let mut name: String = s
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '_' | '-' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let starts_ok = name.starts_with(|c: char| c.is_alphabetic() || c == '_');
+    if !starts_ok || name.to_ascii_lowercase().starts_with("xml") {
+        name.insert(0, '_');
+    }
+    name
+}
+
+fn text(v: &Value) -> String {
+    match v {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        v => v.to_string(),
+    }
+}
+
+/// 转义特殊字符；XML 1.0 不允许的字符（大部分控制字符、U+FFFE、U+FFFF）替换为 U+FFFD
+///
+/// 解析器会把 `\r` 规范化为换行，把属性值中的 `\n`、`\t` 规范化为空格，因此写成字符引用。
+fn escape(s: &str, attribute: bool) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            '\'' => ret.push_str("&apos;"),
+            '\r' => ret.push_str("&#13;"),
+            '\n' if attribute => ret.push_str("&#10;"),
+            '\t' if attribute => ret.push_str("&#9;"),
+            '\t' | '\n' => ret.push(c),
+            '\u{0}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}' => ret.push('\u{fffd}'),
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_xml() -> Result<()> {
+        let ret = vec![json!({"Name": "Tom & \"Jerry\"", "Kit Number": "1", "1st": "<a>"})];
+        let opts = XmlOptions {
+            root: "players".to_string(),
+            row: "player".to_string(),
+            attributes: false,
+        };
+        assert_eq!(
+            to_xml(&ret, &opts)?,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<players>
+  <player>
+    <Name>Tom &amp; &quot;Jerry&quot;</Name>
+    <Kit_Number>1</Kit_Number>
+    <_1st>&lt;a&gt;</_1st>
+  </player>
+</players>
+"#
+        );
+
+        let opts = XmlOptions {
+            attributes: true,
+            ..opts
+        };
+        assert_eq!(
+            to_xml(&ret, &opts)?,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<players>
+  <player Name="Tom &amp; &quot;Jerry&quot;" Kit_Number="1" _1st="&lt;a&gt;"/>
+</players>
+"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_xml_control_characters() -> Result<()> {
+        let ret = vec![json!({"a": "\u{1}x\u{ffff}", "b": "1\n2\t3\r\n"})];
+        let opts = XmlOptions::default();
+        let xml = to_xml(&ret, &opts)?;
+        assert!(xml.contains("<a>\u{fffd}x\u{fffd}</a>"));
+        assert!(xml.contains("<b>1\n2\t3&#13;\n</b>"));
+
+        let opts = XmlOptions {
+            attributes: true,
+            ..opts
+        };
+        let xml = to_xml(&ret, &opts)?;
+        assert!(xml.contains("<record a=\"\u{fffd}x\u{fffd}\" b=\"1&#10;2&#9;3&#13;&#10;\"/>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_xml_names() {
+        assert_eq!(xml_name("Kit Number"), "Kit_Number");
+        assert_eq!(xml_name("xmlns"), "_xmlns");
+        assert_eq!(xml_name("-x"), "_-x");
+        assert_eq!(xml_name(""), "_");
+
+        let ret = vec![json!({"a b": "1", "a_b": "2"})];
+        let names = field_names(&ret);
+        assert_eq!(names["a b"], "a_b");
+        assert_eq!(names["a_b"], "a_b_2");
+
+        let opts = XmlOptions {
+            root: "bad root".to_string(),
+            ..Default::default()
+        };
+        assert!(to_xml(&ret, &opts).is_err());
+    }
+}
@@ -1,5 +1,10 @@
 use anyhow::Result;
-use std::{fs::File, io::Read};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use std::{
+    fs::File,
+    io::{Read, Write},
+};
 
 pub fn get_reader(input: &str) -> Result<Box<dyn Read>> {
     if input == "-" {
@@ -9,6 +14,35 @@ pub fn get_reader(input: &str) -> Result<Box<dyn Read>> {
     }
 }
 
+pub fn get_writer(output: &str) -> Result<Box<dyn Write>> {
+    if output == "-" {
+        Ok(Box::new(std::io::stdout()))
+    } else {
+        Ok(Box::new(File::create(output)?))
+    }
+}
+
+/// A ChaCha20 generator, seeded from the OS unless a seed is given for
+/// reproducible output.
+pub fn get_rng(seed: Option<u64>) -> ChaCha20Rng {
+    match seed {
+        Some(seed) => ChaCha20Rng::seed_from_u64(seed),
+        None => ChaCha20Rng::from_entropy(),
+    }
+}
+
+/// Read a password or secret, dropping the newline an `echo` or editor adds.
+pub fn get_secret(input: &str) -> Result<Vec<u8>> {
+    let mut content = get_content(input)?;
+    if content.ends_with(b"\n") {
+        content.pop();
+        if content.ends_with(b"\r") {
+            content.pop();
+        }
+    }
+    Ok(content)
+}
+
 pub fn get_content(input: &str) -> Result<Vec<u8>> {
     let mut reader = get_reader(input)?;
     let mut buf = Vec::new();